@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
+
+use crate::submodule::{self, Detail, Scan};
+
+/// Bumped whenever the file layout changes, discarding older caches.
+const VERSION: u32 = 2;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn the cache off for the rest of this process (`--no-cache`).
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether scans are cached, i.e. neither `--no-cache` nor `dom.cache=false`.
+pub fn enabled(repo: &Repository) -> bool {
+    let configured = repo
+        .config()
+        .and_then(|c| c.get_bool("dom.cache"))
+        .unwrap_or(true);
+    configured && !DISABLED.load(Ordering::Relaxed)
+}
+
+/// Where the status cache lives for `repo`.
+pub fn path(repo: &Repository) -> PathBuf {
+    repo.path().join("dom").join("status-cache.json")
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct File {
+    version: u32,
+    submodules: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    /// The submodule state the scan was taken from.
+    stamp: Stamp,
+    detail: Detail,
+    scan: Scan,
+}
+
+/// Scan results from earlier runs, shared between the threads gathering
+/// submodule info.
+///
+/// An entry is reused while the submodule's HEAD, branch, upstream and index
+/// are unchanged. That catches commits, checkouts, fetches and staging, but
+/// not edits to the worktree alone, so commands that must be exact (`status`,
+/// `--watch`) bypass the cache.
+pub struct Cache {
+    path: PathBuf,
+    submodules: Mutex<BTreeMap<String, Entry>>,
+    changed: AtomicBool,
+}
+
+impl Cache {
+    /// Load the cache, or `None` when it's turned off by `--no-cache` or
+    /// `dom.cache=false`. A missing or unreadable cache starts out empty.
+    pub fn load(repo: &Repository) -> Option<Cache> {
+        if !enabled(repo) {
+            return None;
+        }
+
+        let path = path(repo);
+        let file = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<File>(&bytes).ok())
+            .filter(|file| file.version == VERSION)
+            .unwrap_or_default();
+
+        Some(Cache {
+            path,
+            submodules: Mutex::new(file.submodules),
+            changed: AtomicBool::new(false),
+        })
+    }
+
+    /// The scan for submodule `name`, reused from the cache when nothing has
+    /// changed since it was taken.
+    pub fn scan(&self, name: &str, repo: &Repository, detail: Detail) -> Scan {
+        // Stamp first so changes made during the scan invalidate it
+        let Some(stamp) = stamp(repo) else {
+            return submodule::scan(repo, detail);
+        };
+
+        let cached = self.submodules.lock().unwrap().get(name).cloned();
+        if let Some(entry) = cached
+            && entry.stamp == stamp
+            && entry.detail.covers(detail)
+        {
+            return entry.scan;
+        }
+
+        let scan = submodule::scan(repo, detail);
+        self.submodules.lock().unwrap().insert(
+            name.to_string(),
+            Entry {
+                stamp,
+                detail,
+                scan,
+            },
+        );
+        self.changed.store(true, Ordering::Relaxed);
+        scan
+    }
+
+    /// Write the cache back if any entry changed.
+    pub fn save(self) -> Result<()> {
+        if !self.changed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let file = File {
+            version: VERSION,
+            submodules: self.submodules.into_inner().unwrap(),
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename so a concurrent reader never sees half a file
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&file)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// What a scan is keyed on: the submodule's HEAD, the branch it's on and that
+/// branch's upstream, and the index's size and modification time. Stored as
+/// is rather than hashed so the file reads the same across builds.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    head: Option<String>,
+    branch: Option<String>,
+    upstream: Option<String>,
+    index: Option<(u64, u32, u64)>,
+}
+
+/// Stamp `repo` from its refs and one `stat` of the index, so checking an
+/// entry costs far less than the scan it saves.
+fn stamp(repo: &Repository) -> Option<Stamp> {
+    repo.workdir()?;
+
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .filter(|h| h.is_branch())
+        .and_then(|h| h.shorthand())
+        .map(str::to_string);
+    let upstream = branch.as_ref().and_then(|b| {
+        repo.refname_to_id(&format!("refs/remotes/origin/{}", b))
+            .ok()
+    });
+
+    let index = fs::metadata(repo.path().join("index")).ok().map(|meta| {
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        (modified.as_secs(), modified.subsec_nanos(), meta.len())
+    });
+
+    Some(Stamp {
+        head: head.and_then(|h| h.target()).map(|oid| oid.to_string()),
+        branch,
+        upstream: upstream.map(|oid| oid.to_string()),
+        index,
+    })
+}
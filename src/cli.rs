@@ -1,9 +1,12 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use clap_complete::Shell;
 use clap_complete::engine::ArgValueCompleter;
 use std::path::PathBuf;
 
+use crate::commands::sync::Source;
 use crate::config::CommitMode;
+use crate::manifest::ManifestFormat;
+use crate::submodule::{Filter, State};
 
 /// Return submodule names for shell completion by reading .gitmodules.
 fn complete_submodule_name(current: &std::ffi::OsStr) -> Vec<clap_complete::CompletionCandidate> {
@@ -27,6 +30,43 @@ fn complete_submodule_name(current: &std::ffi::OsStr) -> Vec<clap_complete::Comp
         .collect()
 }
 
+/// Return group names for shell completion by reading .gitmodules.
+fn complete_group(current: &std::ffi::OsStr) -> Vec<clap_complete::CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let Ok(repo) = git2::Repository::open_from_env() else {
+        return Vec::new();
+    };
+    let Ok(submodules) = repo.submodules() else {
+        return Vec::new();
+    };
+    let mut groups: Vec<String> = submodules
+        .iter()
+        .flat_map(|sm| crate::submodule::groups(&repo, sm.name().unwrap_or("")))
+        .filter(|g| g.starts_with(current.as_ref()))
+        .collect();
+    groups.sort();
+    groups.dedup();
+    groups
+        .into_iter()
+        .map(clap_complete::CompletionCandidate::new)
+        .collect()
+}
+
+/// Return `ls` field names for shell completion.
+fn complete_field(current: &std::ffi::OsStr) -> Vec<clap_complete::CompletionCandidate> {
+    let current = current.to_string_lossy();
+    // Complete the last entry of a comma-separated list, and after a sort's `-`
+    let (done, last) = match current.rfind(',') {
+        Some(i) => current.split_at(i + 1),
+        None => current.split_at(usize::from(current.starts_with('-'))),
+    };
+    crate::template::FIELDS
+        .iter()
+        .filter(|f| f.starts_with(last))
+        .map(|f| clap_complete::CompletionCandidate::new(format!("{}{}", done, f)))
+        .collect()
+}
+
 #[derive(Parser)]
 #[command(
     name = "git-dom",
@@ -38,52 +78,301 @@ pub struct Cli {
     #[arg(long = "no-colour", global = true)]
     pub no_colour: bool,
 
+    /// Don't read or write the status cache in .git/dom
+    #[arg(long = "no-cache", global = true)]
+    pub no_cache: bool,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+/// Group and state flags that narrow the submodules a command works on.
+#[derive(Args)]
+pub struct Selection {
+    /// Only operate on submodules in this group (repeatable)
+    #[arg(
+        long,
+        short = 'g',
+        value_name = "GROUP",
+        add = ArgValueCompleter::new(complete_group)
+    )]
+    pub group: Vec<String>,
+
+    /// Only operate on submodules with uncommitted changes
+    #[arg(long)]
+    pub dirty: bool,
+
+    /// Only operate on submodules with commits not on their upstream
+    #[arg(long)]
+    pub ahead: bool,
+
+    /// Only operate on submodules whose upstream has new commits
+    #[arg(long)]
+    pub behind: bool,
+
+    /// Only operate on submodules with a detached HEAD
+    #[arg(long)]
+    pub detached: bool,
+
+    /// Only operate on submodules that haven't been initialised
+    #[arg(long, alias = "uninitialised")]
+    pub uninitialized: bool,
+}
+
+impl Selection {
+    /// A filter for `selectors` narrowed by these flags.
+    pub fn filter(&self, selectors: Vec<String>) -> Filter {
+        Filter::new(selectors, self.group.clone(), self.states())
+    }
+
+    /// The state predicates requested on the command line.
+    fn states(&self) -> Vec<State> {
+        [
+            (self.dirty, State::Dirty),
+            (self.ahead, State::Ahead),
+            (self.behind, State::Behind),
+            (self.detached, State::Detached),
+            (self.uninitialized, State::Uninitialised),
+        ]
+        .into_iter()
+        .filter_map(|(set, state)| set.then_some(state))
+        .collect()
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Command {
     /// List all submodules
     Ls {
-        /// Target a specific submodule by name
+        /// Submodule names, paths or glob patterns (defaults to all)
         #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
-        name: Option<String>,
+        names: Vec<String>,
+
+        /// Show full commit IDs, authors and dates
+        #[arg(long, short = 'l')]
+        long: bool,
+
+        /// Print each submodule using a template with %(field) placeholders
+        #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["long", "columns"])]
+        format: Option<String>,
+
+        /// Sort by a field (prefix with - for descending order)
+        #[arg(long, value_name = "FIELD", add = ArgValueCompleter::new(complete_field))]
+        sort: Option<String>,
+
+        /// Print a table of these fields (comma-separated)
+        #[arg(
+            long,
+            value_name = "FIELDS",
+            value_delimiter = ',',
+            conflicts_with = "long",
+            add = ArgValueCompleter::new(complete_field)
+        )]
+        columns: Vec<String>,
+
+        /// Number of submodules to inspect in parallel (defaults to dom.jobs)
+        #[arg(long, short = 'j')]
+        jobs: Option<usize>,
+
+        /// Keep running and refresh the list whenever files change
+        #[arg(long, short = 'w')]
+        watch: bool,
+
+        #[command(flatten)]
+        selection: Selection,
     },
 
     /// Show rich status for submodules
     Status {
-        /// Target a specific submodule by name
+        /// Submodule names, paths or glob patterns (defaults to all)
         #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
-        name: Option<String>,
+        names: Vec<String>,
+
+        /// Show full commit IDs, authors and dates
+        #[arg(long, short = 'l')]
+        long: bool,
+
+        /// Print a stable, line-oriented format for scripts
+        #[arg(long, conflicts_with = "long")]
+        porcelain: bool,
+
+        /// Exit with a non-zero code when any submodule needs attention
+        #[arg(long)]
+        exit_code: bool,
+
+        /// Number of submodules to inspect in parallel (defaults to dom.jobs)
+        #[arg(long, short = 'j')]
+        jobs: Option<usize>,
+
+        /// Keep running and refresh the status whenever files change
+        #[arg(long, short = 'w', conflicts_with = "exit_code")]
+        watch: bool,
+
+        #[command(flatten)]
+        selection: Selection,
     },
 
     /// Add a submodule with Go-style path convention
     Clone {
         /// URL, host/user/repo, or owner/repo (defaults to github.com)
-        url: String,
+        #[arg(required_unless_present = "from")]
+        url: Option<String>,
+
+        /// Clone every entry in a manifest file (`-` for stdin)
+        #[arg(long, value_name = "FILE", conflicts_with = "url")]
+        from: Option<String>,
+
+        /// Number of parallel clones when using --from
+        #[arg(long, short = 'j', default_value_t = 4)]
+        jobs: usize,
 
         /// Skip commit prompt even when running interactively
         #[arg(long)]
         no_commit: bool,
     },
 
+    /// Initialise submodules and check out their recorded commits
+    Init {
+        /// Submodule names, paths or glob patterns (defaults to all)
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        /// Number of submodules to clone in parallel
+        #[arg(long, short = 'j', default_value_t = 4)]
+        jobs: usize,
+
+        /// Also initialise nested submodules
+        #[arg(long)]
+        recursive: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Reattach detached submodule HEADs to branches
+    Attach {
+        /// Submodule names, paths or glob patterns (defaults to all)
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Create a topic branch in the parent and submodules
+    Branch {
+        /// Branch name
+        branch: String,
+
+        /// Submodule names, paths or glob patterns (defaults to all)
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Check out a branch in the parent and every submodule that has it
+    Switch {
+        /// Branch name
+        branch: String,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Move submodule worktrees to the commits recorded in the parent
+    Checkout {
+        /// Submodule names, paths or glob patterns (defaults to all)
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        /// Use the commits recorded in HEAD rather than the index
+        #[arg(long)]
+        head: bool,
+
+        /// Discard uncommitted changes in submodules that need to move
+        #[arg(long, short = 'f')]
+        force: bool,
+
+        /// Leave submodules with uncommitted changes where they are, with a warning
+        #[arg(long, conflicts_with = "force")]
+        skip_dirty: bool,
+
+        /// Install a post-checkout hook that runs this after branch switches
+        #[arg(long, conflicts_with_all = ["names", "head", "force", "skip_dirty"])]
+        install_hook: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
     /// Fetch and update submodules from upstream
     Pull {
-        /// Target a specific submodule by name
+        /// Submodule names, paths or glob patterns (defaults to all)
         #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
-        name: Option<String>,
+        names: Vec<String>,
 
         /// Commit mode: auto, stage, or prompt
         #[arg(long, value_enum)]
         commit: Option<CommitMode>,
+
+        #[command(flatten)]
+        selection: Selection,
     },
 
     /// Remove a submodule cleanly
     Rm {
-        /// Submodule name or path
+        /// Submodule names, paths or glob patterns (a path only matches
+        /// what's beneath it when it ends in /)
+        #[arg(required = true, add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        /// Skip commit prompt even when running interactively
+        #[arg(long)]
+        no_commit: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Reconcile .gitmodules, .git/config and submodule remotes
+    Sync {
+        /// Submodule names, paths or glob patterns (defaults to all)
         #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
-        name: String,
+        names: Vec<String>,
+
+        /// Source of truth to copy settings from
+        #[arg(long, value_enum, default_value_t = Source::Gitmodules)]
+        from: Source,
+
+        /// Only report drift, failing if any is found
+        #[arg(long)]
+        check: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Replace a submodule with a vendored copy of its files
+    Vendor {
+        /// Submodule names, paths or glob patterns (a path only matches
+        /// what's beneath it when it ends in /)
+        #[arg(required = true, add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        /// Skip commit prompt even when running interactively
+        #[arg(long)]
+        no_commit: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Restore a vendored directory as a submodule at its recorded commit
+    Unvendor {
+        /// Path of the vendored directory
+        path: String,
 
         /// Skip commit prompt even when running interactively
         #[arg(long)]
@@ -92,23 +381,244 @@ pub enum Command {
 
     /// Show changes across submodules
     Diff {
-        /// Target a specific submodule by name
+        /// Submodule names, paths or glob patterns (defaults to all)
         #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
-        name: Option<String>,
+        names: Vec<String>,
 
         /// Show full per-submodule diffs
         #[arg(long)]
         full: bool,
+
+        /// Number of submodules to inspect in parallel (defaults to dom.jobs)
+        #[arg(long, short = 'j')]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Register existing nested clones as submodules
+    Adopt {
+        /// Path to a nested repository
+        #[arg(required_unless_present = "scan")]
+        path: Option<PathBuf>,
+
+        /// Find and adopt every unregistered nested repository
+        #[arg(long, conflicts_with = "path")]
+        scan: bool,
+
+        /// Only list what would be adopted
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+
+        /// Skip commit prompt even when running interactively
+        #[arg(long)]
+        no_commit: bool,
+    },
+
+    /// Add submodules from a repo, west or vcstool manifest
+    Import {
+        /// Manifest file (`-` for stdin)
+        manifest: String,
+
+        /// Manifest format (detected from the file when omitted)
+        #[arg(long, value_enum)]
+        format: Option<ManifestFormat>,
+
+        /// Number of parallel clones
+        #[arg(long, short = 'j', default_value_t = 4)]
+        jobs: usize,
+
+        /// Skip commit prompt even when running interactively
+        #[arg(long)]
+        no_commit: bool,
+    },
+
+    /// Render submodules and their recorded commits as a manifest
+    Export {
+        /// Manifest format
+        #[arg(long, value_enum)]
+        format: ManifestFormat,
+
+        /// Write the manifest to this path (defaults to stdout)
+        #[arg(long, short = 'o', value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        selection: Selection,
     },
 
     /// Run a command in each submodule
     Foreach {
+        /// Only run in submodules matching this name, path or glob (repeatable)
+        #[arg(
+            long,
+            short = 's',
+            value_name = "SELECTOR",
+            add = ArgValueCompleter::new(complete_submodule_name)
+        )]
+        select: Vec<String>,
+
         /// The command to run
         command: Vec<String>,
 
         /// Run in parallel
         #[arg(long)]
         parallel: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Show commits from the parent and every submodule as one stream
+    Log {
+        /// Only include submodules matching this name, path or glob (repeatable)
+        #[arg(
+            long,
+            short = 's',
+            value_name = "SELECTOR",
+            add = ArgValueCompleter::new(complete_submodule_name)
+        )]
+        select: Vec<String>,
+
+        /// Only commits more recent than this date (e.g. "1 week ago")
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+
+        /// Only commits older than this date
+        #[arg(long, value_name = "DATE")]
+        until: Option<String>,
+
+        /// Only commits whose author matches this pattern
+        #[arg(long, value_name = "PATTERN")]
+        author: Option<String>,
+
+        /// Show at most this many commits
+        #[arg(long, short = 'n', value_name = "N")]
+        max_count: Option<usize>,
+
+        /// Print the commits as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Only commits touching these paths, which may be inside submodules
+        #[arg(last = true)]
+        paths: Vec<PathBuf>,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Search files in the parent and every submodule
+    Grep {
+        /// Only search submodules matching this name, path or glob (repeatable)
+        #[arg(
+            long,
+            short = 's',
+            value_name = "SELECTOR",
+            add = ArgValueCompleter::new(complete_submodule_name)
+        )]
+        select: Vec<String>,
+
+        /// Pattern to search for
+        pattern: String,
+
+        /// Only search these paths, which may be inside submodules, or
+        /// files matching these patterns in every repository
+        pathspec: Vec<String>,
+
+        /// Ignore case differences
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
+
+        /// Print only the names of matching files
+        #[arg(long, short = 'l')]
+        files_with_matches: bool,
+
+        /// Prefix matching lines with their line numbers
+        #[arg(long, short = 'n')]
+        line_number: bool,
+
+        /// Search the index instead of the working trees
+        #[arg(long, conflicts_with = "rev")]
+        cached: bool,
+
+        /// Search the parent at this revision, and each submodule at the
+        /// commit recorded there
+        #[arg(long, value_name = "REV")]
+        rev: Option<String>,
+
+        /// Print the matches as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// List the parent commits that moved a submodule's recorded commit
+    History {
+        /// Submodule name or path
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        name: String,
+
+        /// Show at most this many commits
+        #[arg(long, short = 'n', value_name = "N")]
+        max_count: Option<usize>,
+    },
+
+    /// Find the parent commits, branches and tags that include a submodule commit
+    Contains {
+        /// Submodule name or path
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        name: String,
+
+        /// Submodule commit, branch or tag to look for
+        #[arg(value_name = "COMMIT")]
+        commit: String,
+    },
+
+    /// Browse submodules in an interactive dashboard
+    Tui {
+        /// Submodule names, paths or glob patterns (defaults to all)
+        #[arg(add = ArgValueCompleter::new(complete_submodule_name))]
+        names: Vec<String>,
+
+        /// Number of submodules to inspect in parallel (defaults to dom.jobs)
+        #[arg(long, short = 'j')]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Print a one-line submodule summary for shell prompts
+    Prompt {
+        /// Summary with %(total), %(dirty), %(ahead), %(behind), %(detached),
+        /// %(uninitialised) and %(changed) placeholders (defaults to dom.promptFormat)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Milliseconds to wait before giving up (defaults to dom.promptTimeout)
+        #[arg(long, value_name = "MS")]
+        timeout: Option<u64>,
+
+        /// Printed when the summary isn't ready in time (defaults to dom.promptStale)
+        #[arg(long, value_name = "TEXT")]
+        stale: Option<String>,
+
+        /// Gather status to refresh the cache, printing nothing
+        #[arg(long, hide = true)]
+        refresh: bool,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Manage the status cache in .git/dom
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
     },
 
     /// Generate shell completions
@@ -128,3 +638,9 @@ pub enum Command {
         install: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Delete the cached submodule status
+    Clear,
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::commands::clone::{self, PlannedClone};
+
+pub fn run(path: Option<PathBuf>, scan: bool, dry_run: bool, no_commit: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?
+        .to_path_buf();
+
+    let registered: Vec<PathBuf> = repo
+        .submodules()
+        .context("Failed to read submodules")?
+        .iter()
+        .map(|sm| sm.path().to_path_buf())
+        .collect();
+
+    let candidates = if scan {
+        let mut found = Vec::new();
+        scan_nested(&repo, &workdir, &workdir, &registered, &mut found)?;
+        found
+    } else {
+        let path = path.context("Specify a path or --scan")?;
+        let rel = relative_to_workdir(&workdir, &path)?;
+        if registered.contains(&rel) {
+            bail!("{} is already a submodule", rel.display());
+        }
+        if !workdir.join(&rel).join(".git").is_dir() {
+            bail!("{} is not a nested git repository", rel.display());
+        }
+        vec![rel]
+    };
+
+    if candidates.is_empty() {
+        println!("{}", "No unregistered nested repositories found.".green());
+        return Ok(());
+    }
+
+    let mut adopted = Vec::new();
+    let mut failed = 0;
+
+    for rel in &candidates {
+        let sub_path = rel.to_string_lossy().into_owned();
+
+        let url = match origin_url(&workdir.join(rel)) {
+            Ok(url) => url,
+            Err(e) => {
+                failed += 1;
+                eprintln!("  {} {}: {}", "✗".red().bold(), sub_path, e);
+                continue;
+            }
+        };
+
+        if dry_run {
+            println!("  {} {} ({})", "+".green(), sub_path.bold(), url.dimmed());
+            continue;
+        }
+
+        let planned = PlannedClone {
+            git_url: url,
+            sub_path,
+            branch: None,
+            revision: None,
+            groups: Vec::new(),
+        };
+
+        match clone::register(&workdir, &planned) {
+            Ok(()) => {
+                println!(
+                    "  {} {} ({})",
+                    "✓".green().bold(),
+                    planned.sub_path.bold(),
+                    planned.git_url.dimmed()
+                );
+                adopted.push(planned.sub_path);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("  {} {}: {}", "✗".red().bold(), planned.sub_path, e);
+                // The clone is the user's own, so only the registration goes
+                clone::unregister(&workdir, &planned.sub_path);
+            }
+        }
+    }
+
+    // Prompt to commit if we're on a TTY (unless --no-commit)
+    if !dry_run && !adopted.is_empty() && !no_commit && atty::is(atty::Stream::Stdin) {
+        let msg = format!("Adopt submodule(s): {}", adopted.join(", "));
+        clone::commit_batch(&workdir, &msg, &adopted, false, true)?;
+    }
+
+    if failed > 0 {
+        bail!("{} repo(s) could not be adopted", failed);
+    }
+
+    Ok(())
+}
+
+/// Resolve `path` (relative to the current directory) to a path relative to the workdir.
+fn relative_to_workdir(workdir: &Path, path: &Path) -> Result<PathBuf> {
+    let abs = std::fs::canonicalize(path)
+        .with_context(|| format!("Path does not exist: {}", path.display()))?;
+    let workdir = std::fs::canonicalize(workdir)?;
+
+    match abs.strip_prefix(&workdir) {
+        Ok(rel) if !rel.as_os_str().is_empty() => Ok(rel.to_path_buf()),
+        _ => bail!("{} is not inside the repository", path.display()),
+    }
+}
+
+/// The URL of the nested repo's `origin` remote, or of its only remote.
+fn origin_url(path: &Path) -> Result<String> {
+    let nested = Repository::open(path)
+        .with_context(|| format!("Failed to open repo at {}", path.display()))?;
+
+    if nested.head().ok().and_then(|h| h.target()).is_none() {
+        bail!("repository has no commits");
+    }
+
+    let remote = match nested.find_remote("origin") {
+        Ok(remote) => remote,
+        Err(_) => {
+            let remotes = nested.remotes()?;
+            match remotes.iter().flatten().collect::<Vec<_>>().as_slice() {
+                [only] => nested.find_remote(only)?,
+                [] => bail!("repository has no remotes"),
+                _ => bail!("repository has no 'origin' remote and several others"),
+            }
+        }
+    };
+
+    remote
+        .url()
+        .map(|u| u.to_string())
+        .context("remote URL is not valid UTF-8")
+}
+
+/// Walk the worktree collecting nested repositories that aren't registered
+/// submodules. Ignored directories and nested repos aren't descended into.
+fn scan_nested(
+    repo: &Repository,
+    workdir: &Path,
+    dir: &Path,
+    registered: &[PathBuf],
+    found: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        let abs = entry.path();
+        let rel = abs.strip_prefix(workdir).unwrap_or(&abs).to_path_buf();
+
+        if registered.contains(&rel) || repo.is_path_ignored(&rel).unwrap_or(false) {
+            continue;
+        }
+
+        if abs.join(".git").is_dir() {
+            found.push(rel);
+        } else {
+            scan_nested(repo, workdir, &abs, registered, found)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::{BranchType, Oid, Repository};
+
+use crate::submodule::{self, Filter};
+
+pub fn run(filter: Filter) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+
+    let selected = submodule::select(&repo, &filter)?;
+    if selected.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    let max_name = selected
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").len())
+        .max()
+        .unwrap_or(0);
+    let mut any_detached = false;
+
+    for sm in &selected {
+        let name = sm.name().unwrap_or("");
+        // Uninitialised submodules have no HEAD to attach
+        let Ok(sub_repo) = sm.open() else {
+            continue;
+        };
+        if sub_repo.head().is_ok_and(|h| h.is_branch()) {
+            continue;
+        }
+        any_detached = true;
+
+        match attach(&sub_repo, sm.branch(), true)? {
+            Attach::Attached(branch) => println!(
+                "  {} {:<width$}  {}",
+                "✓".green().bold(),
+                name.bold(),
+                branch.cyan(),
+                width = max_name
+            ),
+            Attach::FastForwarded(branch) => println!(
+                "  {} {:<width$}  {} {}",
+                "↑".green().bold(),
+                name.bold(),
+                branch.cyan(),
+                "(fast-forwarded)".dimmed(),
+                width = max_name
+            ),
+            Attach::Detached { commit, reason } => println!(
+                "  {} {:<width$}  {} {}",
+                "✗".red().bold(),
+                name.bold(),
+                format!("detached at {:.7}", commit).yellow(),
+                format!("({})", reason).dimmed(),
+                width = max_name
+            ),
+        }
+    }
+
+    if !any_detached {
+        println!("{}", "No detached submodules.".green());
+    }
+
+    Ok(())
+}
+
+pub(crate) enum Attach {
+    Attached(String),
+    FastForwarded(String),
+    Detached { commit: Oid, reason: String },
+}
+
+/// Attach a detached HEAD to the first branch that can take it without moving
+/// the worktree. The tracked branch (the configured one, else the remote's
+/// default) is tried first and may be fast-forwarded to HEAD when its upstream
+/// already contains it, or created from a remote branch that contains HEAD.
+/// Any other branch is only used when its tip is already HEAD, so unrelated
+/// local branches never move. Without `fast_forward` no branch moves at all.
+pub(crate) fn attach(
+    sub_repo: &Repository,
+    configured: Option<&str>,
+    fast_forward: bool,
+) -> Result<Attach> {
+    let head = sub_repo.head().context("Submodule has no HEAD")?;
+    let oid = head.target().context("Submodule HEAD is not a commit")?;
+
+    let tracked = configured
+        .map(|b| b.to_string())
+        .or_else(|| remote_default_branch(sub_repo));
+
+    let mut candidates: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        if !candidates.iter().any(|c| c == name) {
+            candidates.push(name.to_string());
+        }
+    };
+
+    if let Some(ref b) = tracked {
+        push(b);
+    }
+    let mut others = Vec::new();
+    for branch in sub_repo.branches(None)? {
+        let (branch, kind) = branch?;
+        let Some(name) = branch.name()? else {
+            continue;
+        };
+        let name = match kind {
+            BranchType::Local => name,
+            BranchType::Remote => match name.strip_prefix("origin/") {
+                Some(n) if n != "HEAD" => n,
+                _ => continue,
+            },
+        };
+        others.push(name.to_string());
+    }
+    others.sort();
+    for name in &others {
+        push(name);
+    }
+
+    let mut blocked = Vec::new();
+
+    for branch in &candidates {
+        let is_tracked = tracked.as_ref() == Some(branch);
+        let local_tip = sub_repo
+            .find_branch(branch, BranchType::Local)
+            .ok()
+            .and_then(|b| b.get().target());
+        let remote_tip = sub_repo
+            .find_reference(&format!("refs/remotes/origin/{}", branch))
+            .ok()
+            .and_then(|r| r.target());
+
+        let remote_contains = remote_tip.is_some_and(|t| {
+            t == oid || (is_tracked && sub_repo.graph_descendant_of(t, oid).unwrap_or(false))
+        });
+
+        match local_tip {
+            Some(tip) if tip == oid => {
+                sub_repo.set_head(&format!("refs/heads/{}", branch))?;
+                return Ok(Attach::Attached(branch.clone()));
+            }
+            // Don't drag a branch onto commits its upstream doesn't have
+            Some(tip)
+                if is_tracked
+                    && fast_forward
+                    && (remote_contains || remote_tip.is_none())
+                    && sub_repo.graph_descendant_of(oid, tip)? =>
+            {
+                let reference = format!("refs/heads/{}", branch);
+                sub_repo.reference(&reference, oid, true, "git-dom attach: fast-forward")?;
+                sub_repo.set_head(&reference)?;
+                return Ok(Attach::FastForwarded(branch.clone()));
+            }
+            // Only worth mentioning if the branch is related to HEAD at all
+            Some(tip) if is_tracked && sub_repo.graph_descendant_of(tip, oid)? => {
+                blocked.push(format!("local {} is ahead", branch));
+            }
+            Some(tip) if is_tracked && sub_repo.graph_descendant_of(oid, tip)? => {
+                blocked.push(format!("origin/{} doesn't contain HEAD", branch));
+            }
+            Some(_) => {}
+            None if remote_contains => {
+                let commit = sub_repo.find_commit(oid)?;
+                let mut local = sub_repo.branch(branch, &commit, false)?;
+                local.set_upstream(Some(&format!("origin/{}", branch)))?;
+                sub_repo.set_head(&format!("refs/heads/{}", branch))?;
+                return Ok(Attach::Attached(branch.clone()));
+            }
+            None => {}
+        }
+    }
+
+    let reason = if blocked.is_empty() {
+        "no branch contains HEAD".to_string()
+    } else {
+        blocked.join(", ")
+    };
+    Ok(Attach::Detached {
+        commit: oid,
+        reason,
+    })
+}
+
+/// The branch `origin/HEAD` points at, e.g. `main`.
+pub(crate) fn remote_default_branch(sub_repo: &Repository) -> Option<String> {
+    let head = sub_repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target
+        .strip_prefix("refs/remotes/origin/")
+        .map(|b| b.to_string())
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::{BranchType, Repository};
+
+use crate::submodule::{self, Filter};
+
+pub fn run(branch: String, filter: Filter) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+
+    let selected = submodule::select(&repo, &filter)?;
+
+    let max_name = selected
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").len())
+        .max()
+        .unwrap_or(0)
+        .max("(parent)".len());
+
+    let mut failed = 0;
+    if !report("(parent)", create(&repo, &branch), max_name) {
+        failed += 1;
+    }
+
+    for sm in &selected {
+        let name = sm.name().unwrap_or("");
+        let Ok(sub_repo) = sm.open() else {
+            println!(
+                "  {} {:<max_name$}  {}",
+                "·".dimmed(),
+                name.bold(),
+                "not initialised".dimmed()
+            );
+            continue;
+        };
+        if !report(name, create(&sub_repo, &branch), max_name) {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!("Could not create {} in {} repo(s)", branch, failed);
+    }
+
+    Ok(())
+}
+
+enum Created {
+    New(git2::Oid),
+    Existing,
+}
+
+/// Create `branch` at HEAD unless it already exists.
+fn create(repo: &Repository, branch: &str) -> Result<Created> {
+    if repo.find_branch(branch, BranchType::Local).is_ok() {
+        return Ok(Created::Existing);
+    }
+    let commit = repo.head()?.peel_to_commit()?;
+    repo.branch(branch, &commit, false)?;
+    Ok(Created::New(commit.id()))
+}
+
+/// Print how creating the branch went, returning whether it succeeded.
+fn report(name: &str, result: Result<Created>, width: usize) -> bool {
+    match result {
+        Ok(Created::New(oid)) => println!(
+            "  {} {:<width$}  {}",
+            "✓".green().bold(),
+            name.bold(),
+            format!("created at {:.7}", oid).dimmed()
+        ),
+        Ok(Created::Existing) => println!(
+            "  {} {:<width$}  {}",
+            "·".dimmed(),
+            name.bold(),
+            "already exists".dimmed()
+        ),
+        Err(e) => {
+            eprintln!("  {} {:<width$}  {}", "✗".red().bold(), name.bold(), e);
+            return false;
+        }
+    }
+    true
+}
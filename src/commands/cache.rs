@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use std::fs;
+
+use crate::cache;
+use crate::cli::CacheCommand;
+
+pub fn run(command: CacheCommand) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+
+    match command {
+        CacheCommand::Clear => {
+            let path = cache::path(&repo);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                println!("{} Status cache cleared.", "✓".green().bold());
+            } else {
+                println!("{}", "Status cache is already empty.".dimmed());
+            }
+        }
+    }
+
+    Ok(())
+}
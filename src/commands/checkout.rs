@@ -0,0 +1,182 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::{Repository, StatusOptions};
+use std::fs;
+use std::path::Path;
+
+use crate::commands::attach::{self, Attach};
+use crate::git;
+use crate::submodule::{self, Filter};
+
+/// Marker line identifying a hook written by `checkout --install-hook`.
+const HOOK_MARKER: &str = "# installed by git-dom";
+
+pub fn run(
+    filter: Filter,
+    head: bool,
+    force: bool,
+    skip_dirty: bool,
+    install_hook: bool,
+) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    if install_hook {
+        return write_hook(&repo);
+    }
+
+    let selected = submodule::select(&repo, &filter)?;
+    if selected.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    // Work out which initialised submodules need to move before touching any
+    let mut pending = Vec::new();
+    for sm in &selected {
+        let Ok(sub_repo) = sm.open() else {
+            continue;
+        };
+        let target = if head {
+            submodule::recorded_commit(&repo, sm.path())
+        } else {
+            submodule::staged_commit(&repo, sm.path())
+        };
+        let Some(target) = target else {
+            continue;
+        };
+        let current = sub_repo.head().ok().and_then(|h| h.target());
+        if current != Some(target) {
+            pending.push((sm, sub_repo, current, target));
+        }
+    }
+
+    if pending.is_empty() {
+        println!("{}", "All submodules match the recorded commits.".green());
+        return Ok(());
+    }
+
+    if !force {
+        let dirty: Vec<String> = pending
+            .iter()
+            .filter(|(_, sub_repo, _, _)| is_dirty(sub_repo))
+            .map(|(sm, _, _, _)| sm.name().unwrap_or("").to_string())
+            .collect();
+        if !dirty.is_empty() && !skip_dirty {
+            bail!(
+                "Uncommitted changes in {}; commit or stash them first, or use --force",
+                dirty.join(", ")
+            );
+        }
+        if !dirty.is_empty() {
+            eprintln!(
+                "{} uncommitted changes in {}; left at their current commits",
+                "warning:".yellow().bold(),
+                dirty.join(", ")
+            );
+            pending.retain(|(sm, _, _, _)| !dirty.iter().any(|d| d == sm.name().unwrap_or("")));
+        }
+    }
+
+    let max_name = pending
+        .iter()
+        .map(|(sm, _, _, _)| sm.name().unwrap_or("").len())
+        .max()
+        .unwrap_or(0);
+
+    for (sm, sub_repo, current, target) in &pending {
+        let name = sm.name().unwrap_or("");
+        let sub_dir = workdir.join(sm.path());
+        let sha = target.to_string();
+
+        if sub_repo.find_commit(*target).is_err() {
+            git::run(&sub_dir, &["fetch", "--quiet", "origin"])?;
+            if sub_repo.find_commit(*target).is_err() {
+                git::run(&sub_dir, &["fetch", "--quiet", "origin", &sha])?;
+            }
+        }
+
+        let mut args = vec!["checkout", "--quiet", "--detach"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&sha);
+        git::run(&sub_dir, &args)?;
+
+        // Put HEAD back on a branch already at the commit; this also runs from
+        // the post-checkout hook, so it never moves one
+        let on = match attach::attach(sub_repo, sm.branch(), false)? {
+            Attach::Attached(branch) | Attach::FastForwarded(branch) => branch.cyan(),
+            Attach::Detached { .. } => "(detached)".yellow(),
+        };
+
+        let from = current
+            .map(|c| format!("{:.7}", c))
+            .unwrap_or_else(|| "-------".to_string());
+        println!(
+            "  {} {:<width$}  {} → {}  {}",
+            "✓".green().bold(),
+            name.bold(),
+            from.dimmed(),
+            format!("{:.7}", target).yellow(),
+            on,
+            width = max_name
+        );
+    }
+
+    Ok(())
+}
+
+fn is_dirty(repo: &Repository) -> bool {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    repo.statuses(Some(&mut opts))
+        .map(|s| !s.is_empty())
+        .unwrap_or(false)
+}
+
+/// Install a post-checkout hook that runs `git dom checkout` after branch switches.
+fn write_hook(repo: &Repository) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+    // Respects core.hooksPath
+    let hooks = git::output(workdir, &["rev-parse", "--git-path", "hooks"])?;
+    let path = workdir.join(hooks.trim()).join("post-checkout");
+
+    if path.exists() && !fs::read_to_string(&path)?.contains(HOOK_MARKER) {
+        bail!(
+            "{} already exists; add `git dom checkout` to it by hand",
+            path.display()
+        );
+    }
+
+    // $3 is 1 for branch checkouts and 0 for file checkouts. The parent's
+    // checkout has already happened by now, so never report it as failed.
+    let script = format!(
+        "#!/bin/sh\n{}\nif [ \"$3\" = 1 ]; then\n    git dom checkout --skip-dirty\nfi\nexit 0\n",
+        HOOK_MARKER
+    );
+    write_executable(&path, &script)?;
+    println!("{}", path.display());
+
+    Ok(())
+}
+
+fn write_executable(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
@@ -1,22 +1,34 @@
 use anyhow::{Context, Result, bail};
 use colored::Colorize;
 use git2::Repository;
-use std::io::Write;
+use std::io::{Read, Write};
+use std::path::Path;
 use std::process::Command;
 
 use crate::config::Config;
+use crate::git;
+use crate::manifest::normalize_path;
+use crate::parallel;
 
-pub fn run(url: String, no_commit: bool) -> Result<()> {
+pub fn run(url: Option<String>, from: Option<String>, jobs: usize, no_commit: bool) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
     let config = Config::load(&repo)?;
 
-    // Parse the URL to determine the submodule path
-    let (git_url, sub_path) = parse_url_and_path(&url, &config.root)?;
-
     let workdir = repo
         .workdir()
         .context("Bare repositories are not supported")?;
 
+    match (url, from) {
+        (_, Some(from)) => run_batch(workdir, &config, &from, jobs, no_commit),
+        (Some(url), None) => run_single(workdir, &config, &url, no_commit),
+        (None, None) => bail!("Specify a URL or --from <FILE>"),
+    }
+}
+
+fn run_single(workdir: &Path, config: &Config, url: &str, no_commit: bool) -> Result<()> {
+    // Parse the URL to determine the submodule path
+    let (git_url, sub_path) = parse_url_and_path(url, &config.root)?;
+
     let full_path = workdir.join(&sub_path);
 
     if full_path.exists() {
@@ -46,35 +58,381 @@ pub fn run(url: String, no_commit: bool) -> Result<()> {
 
     // Prompt to commit if we're on a TTY (unless --no-commit)
     if !no_commit && atty::is(atty::Stream::Stdin) {
+        let msg = format!("Add submodule: {}", sub_path);
+        commit_batch(workdir, &msg, &[sub_path], false, true)?;
+    }
+
+    Ok(())
+}
+
+/// A single line of a `clone --from` manifest.
+#[derive(Debug, PartialEq)]
+struct ManifestEntry {
+    url: String,
+    branch: Option<String>,
+    path: Option<String>,
+    groups: Vec<String>,
+}
+
+/// A submodule to add, resolved to a concrete URL and path.
+pub(crate) struct PlannedClone {
+    pub(crate) git_url: String,
+    pub(crate) sub_path: String,
+    /// Branch to clone and record in .gitmodules
+    pub(crate) branch: Option<String>,
+    /// Branch, tag or commit to check out after cloning
+    pub(crate) revision: Option<String>,
+    pub(crate) groups: Vec<String>,
+}
+
+fn run_batch(
+    workdir: &Path,
+    config: &Config,
+    from: &str,
+    jobs: usize,
+    no_commit: bool,
+) -> Result<()> {
+    let content = if from == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("Failed to read manifest from stdin")?;
+        buf
+    } else {
+        std::fs::read_to_string(from).with_context(|| format!("Failed to read {}", from))?
+    };
+
+    let entries = parse_manifest(&content)?;
+    if entries.is_empty() {
+        bail!("No entries found in {}", from);
+    }
+
+    let mut planned = Vec::new();
+    for entry in entries {
+        let (git_url, default_path) = parse_url_and_path(&entry.url, &config.root)?;
+        planned.push(PlannedClone {
+            git_url,
+            sub_path: entry.path.unwrap_or(default_path),
+            branch: entry.branch,
+            revision: None,
+            groups: entry.groups,
+        });
+    }
+
+    let batch = add_batch(workdir, &planned, jobs)?;
+
+    // A manifest piped on stdin leaves nothing to answer the prompt with
+    let msg = format!("Add submodules: {}", batch.added.join(", "));
+    commit_batch(workdir, &msg, &batch.added, no_commit, from != "-")?;
+    batch.check()
+}
+
+/// The submodules [`add_batch`] added, and how many it couldn't.
+pub(crate) struct Batch {
+    pub(crate) added: Vec<String>,
+    pub(crate) failed: usize,
+}
+
+impl Batch {
+    /// Fail if any submodule couldn't be added, once the rest are committed.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.failed > 0 {
+            bail!("{} submodule(s) failed", self.failed);
+        }
+        Ok(())
+    }
+}
+
+/// Clone every planned submodule in parallel and register the ones that
+/// succeeded.
+pub(crate) fn add_batch(workdir: &Path, planned: &[PlannedClone], jobs: usize) -> Result<Batch> {
+    for (i, p) in planned.iter().enumerate() {
+        if workdir.join(&p.sub_path).exists() {
+            bail!("Path already exists: {}", p.sub_path);
+        }
+        if planned[..i].iter().any(|q| q.sub_path == p.sub_path) {
+            bail!("Duplicate path in manifest: {}", p.sub_path);
+        }
+    }
+
+    println!(
+        "{} {} submodule(s) with {} job(s)",
+        "Cloning".bold(),
+        planned.len(),
+        jobs
+    );
+
+    // Clone in parallel, then register sequentially since `git submodule add`
+    // writes to .gitmodules and the index.
+    let results = parallel::map(planned, jobs, |p| {
+        let result = clone_worktree(workdir, p);
+        match &result {
+            Ok(()) => println!("  {} {}", "✓".green().bold(), p.sub_path),
+            Err(e) => eprintln!("  {} {}: {}", "✗".red().bold(), p.sub_path, e),
+        }
+        result
+    });
+
+    let mut added = Vec::new();
+    let mut failed = 0;
+
+    for (p, result) in planned.iter().zip(results) {
+        if result.is_err() {
+            failed += 1;
+            continue;
+        }
+        match register(workdir, p) {
+            Ok(()) => added.push(p.sub_path.clone()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("  {} {}: {}", "✗".red().bold(), p.sub_path, e);
+                // Otherwise the clone stays behind as an embedded repository
+                discard(workdir, p);
+            }
+        }
+    }
+
+    if added.is_empty() {
+        bail!("No submodules were added");
+    }
+
+    println!("{} Added {} submodule(s).", "✓".green().bold(), added.len());
+
+    Ok(Batch { added, failed })
+}
+
+/// Commit `paths` and `.gitmodules` to the parent as one commit, leaving
+/// anything else that's staged out of it.
+///
+/// Prompts first when `can_prompt` and stdin is a TTY, otherwise commits
+/// directly unless `no_commit` is set.
+pub(crate) fn commit_batch(
+    workdir: &Path,
+    msg: &str,
+    paths: &[String],
+    no_commit: bool,
+    can_prompt: bool,
+) -> Result<()> {
+    if no_commit {
+        println!("{}", "Changes left staged.".dimmed());
+        return Ok(());
+    }
+
+    if can_prompt && atty::is(atty::Stream::Stdin) {
         print!("Commit to parent repo? [Y/n] ");
         std::io::stdout().flush()?;
 
         let mut input = String::new();
         std::io::stdin().read_line(&mut input)?;
 
-        if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("y") {
-            let msg = format!("Add submodule: {}", sub_path);
-            Command::new("git")
-                .args(["add", "-A"])
-                .current_dir(workdir)
-                .output()?;
-            Command::new("git")
-                .args(["commit", "-m", &msg])
-                .current_dir(workdir)
-                .output()?;
-            println!(
-                "{} {}",
-                "✓".green().bold(),
-                format!("Committed: {}", msg).dimmed()
-            );
-        } else {
+        if !(input.trim().is_empty() || input.trim().eq_ignore_ascii_case("y")) {
             println!("{}", "Changes left staged.".dimmed());
+            return Ok(());
         }
     }
 
+    let mut paths = paths.to_vec();
+    paths.push(".gitmodules".to_string());
+    commit_paths(workdir, msg, &paths)?;
+
+    println!(
+        "{} {}",
+        "✓".green().bold(),
+        format!("Committed: {}", msg).dimmed()
+    );
     Ok(())
 }
 
+/// Commit what's staged under `paths` and nothing else.
+///
+/// `git commit -- <paths>` can't commit a gitlink turning into a directory,
+/// so the commit is made from a scratch index holding HEAD plus those paths.
+pub(crate) fn commit_paths(workdir: &Path, msg: &str, paths: &[String]) -> Result<()> {
+    let repo = Repository::open(workdir)?;
+    let staged = repo.index()?;
+    let file = repo.path().join("dom").join("commit-index");
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _ = std::fs::remove_file(&file);
+
+    let within = |path: &[u8]| {
+        paths.iter().any(|p| {
+            let p = p.as_bytes();
+            path.strip_prefix(p)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+        })
+    };
+
+    let mut index = git2::Index::open(&file)?;
+    if let Ok(tree) = repo.head().and_then(|h| h.peel_to_tree()) {
+        index.read_tree(&tree)?;
+    }
+    let stale: Vec<Vec<u8>> = index
+        .iter()
+        .filter(|e| within(&e.path))
+        .map(|e| e.path)
+        .collect();
+    for path in stale {
+        index.remove(Path::new(&*String::from_utf8_lossy(&path)), 0)?;
+    }
+    for entry in staged.iter().filter(|e| within(&e.path)) {
+        index.add(&entry)?;
+    }
+    index.write()?;
+
+    let result = git::run_with_index(workdir, &file, &["commit", "--quiet", "-m", msg]);
+    let _ = std::fs::remove_file(&file);
+    result
+}
+
+/// Clone a planned entry straight into its worktree path, removing the clone
+/// again if its revision can't be checked out.
+pub(crate) fn clone_worktree(workdir: &Path, p: &PlannedClone) -> Result<()> {
+    let mut args = vec!["clone", "--quiet"];
+    if let Some(ref branch) = p.branch {
+        args.extend(["--branch", branch]);
+    }
+    args.extend(["--", &p.git_url, &p.sub_path]);
+    git::run(workdir, &args)?;
+
+    if let Some(ref revision) = p.revision {
+        let sub_dir = workdir.join(&p.sub_path);
+        // Commits that aren't reachable from the default refs need fetching first
+        let checkout = || -> Result<()> {
+            if git::run(&sub_dir, &["checkout", "--quiet", revision]).is_err() {
+                git::run(&sub_dir, &["fetch", "--quiet", "origin", revision])?;
+                git::run(&sub_dir, &["checkout", "--quiet", "FETCH_HEAD"])?;
+            }
+            Ok(())
+        };
+        if let Err(e) = checkout() {
+            // Otherwise the clone stays behind as an embedded repository
+            let _ = std::fs::remove_dir_all(&sub_dir);
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Register an already-cloned worktree as a submodule and move its git dir
+/// into .git/modules.
+pub(crate) fn register(workdir: &Path, p: &PlannedClone) -> Result<()> {
+    // A revision that names a remote branch is tracked like an explicit branch
+    let branch = p.branch.as_deref().or_else(|| {
+        let revision = p.revision.as_deref()?;
+        let sub_repo = Repository::open(workdir.join(&p.sub_path)).ok()?;
+        sub_repo
+            .find_reference(&format!("refs/remotes/origin/{}", revision))
+            .ok()
+            .map(|_| revision)
+    });
+
+    let mut args = vec!["submodule", "add", "--quiet"];
+    if let Some(branch) = branch {
+        args.extend(["-b", branch]);
+    }
+    args.extend(["--", &p.git_url, &p.sub_path]);
+    git::run(workdir, &args)?;
+
+    git::run(workdir, &["submodule", "absorbgitdirs", "--", &p.sub_path])?;
+
+    if !p.groups.is_empty() {
+        // `git submodule add` names the submodule after its path
+        let key = format!("submodule.{}.group", p.sub_path);
+        for group in &p.groups {
+            git::run(
+                workdir,
+                &["config", "--file", ".gitmodules", "--add", &key, group],
+            )?;
+        }
+        git::run(workdir, &["add", ".gitmodules"])?;
+    }
+
+    Ok(())
+}
+
+/// Undo whatever [`register`] got through for a clone that failed to register,
+/// and delete the clone.
+fn discard(workdir: &Path, p: &PlannedClone) {
+    unregister(workdir, &p.sub_path);
+    let _ = std::fs::remove_dir_all(workdir.join(".git").join("modules").join(&p.sub_path));
+    let _ = std::fs::remove_dir_all(workdir.join(&p.sub_path));
+}
+
+/// Drop the gitlink and `.gitmodules` entry a failed [`register`] may have
+/// left staged, keeping the worktree.
+pub(crate) fn unregister(workdir: &Path, sub_path: &str) {
+    let section = format!("submodule.{}", sub_path);
+    let _ = git::run(
+        workdir,
+        &[
+            "rm",
+            "--cached",
+            "--quiet",
+            "--ignore-unmatch",
+            "--",
+            sub_path,
+        ],
+    );
+    if git::run(
+        workdir,
+        &[
+            "config",
+            "--file",
+            ".gitmodules",
+            "--remove-section",
+            &section,
+        ],
+    )
+    .is_ok()
+    {
+        let _ = git::run(workdir, &["add", "--", ".gitmodules"]);
+    }
+}
+
+/// Parse a `clone --from` manifest.
+///
+/// One URL or shorthand per line, optionally followed by `branch=`, `path=`
+/// and `group=` annotations. `group` may be repeated or comma-separated.
+/// Blank lines and `#` comments are ignored.
+fn parse_manifest(content: &str) -> Result<Vec<ManifestEntry>> {
+    let mut entries = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let mut tokens = line.split_whitespace().take_while(|t| !t.starts_with('#'));
+
+        let Some(url) = tokens.next() else {
+            continue;
+        };
+
+        let mut entry = ManifestEntry {
+            url: url.to_string(),
+            branch: None,
+            path: None,
+            groups: Vec::new(),
+        };
+
+        for token in tokens {
+            match token.split_once('=') {
+                Some(("branch", v)) if !v.is_empty() => entry.branch = Some(v.to_string()),
+                Some(("path", v)) if !v.is_empty() => entry.path = Some(normalize_path(v)),
+                Some(("group", v)) => entry.groups.extend(
+                    v.split(',')
+                        .filter(|g| !g.is_empty())
+                        .map(|g| g.to_string()),
+                ),
+                _ => bail!("line {}: unrecognised annotation '{}'", i + 1, token),
+            }
+        }
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
 /// Parse clone input into a full git URL and a local path.
 ///
 /// Supported inputs:
@@ -124,7 +482,7 @@ fn url_to_path(url: &str, root: &str) -> Result<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_url_and_path;
+    use super::{ManifestEntry, parse_manifest, parse_url_and_path};
 
     #[test]
     fn parses_owner_repo_slug() {
@@ -155,4 +513,37 @@ mod tests {
         assert_eq!(git_url, "git@github.com:tokio-rs/tokio.git");
         assert_eq!(path, "src/github.com/tokio-rs/tokio");
     }
+
+    #[test]
+    fn parses_manifest_with_annotations() {
+        let entries = parse_manifest(
+            "# deps\n\ntokio-rs/tokio branch=master group=runtime,async\n\
+             git@github.com:serde-rs/serde.git path=vendor/serde/ group=core # pinned\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ManifestEntry {
+                    url: "tokio-rs/tokio".to_string(),
+                    branch: Some("master".to_string()),
+                    path: None,
+                    groups: vec!["runtime".to_string(), "async".to_string()],
+                },
+                ManifestEntry {
+                    url: "git@github.com:serde-rs/serde.git".to_string(),
+                    branch: None,
+                    path: Some("vendor/serde".to_string()),
+                    groups: vec!["core".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_manifest_annotation() {
+        let err = parse_manifest("tokio-rs/tokio\nserde-rs/serde rev=v1\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unrecognised annotation 'rev=v1'");
+    }
 }
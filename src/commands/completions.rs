@@ -24,10 +24,28 @@ _git-dom() {
         'ls:List all submodules'
         'status:Show rich status for submodules'
         'clone:Add a submodule with Go-style path convention'
+        'init:Initialise submodules and check out their recorded commits'
+        'attach:Reattach detached submodule HEADs to branches'
+        'branch:Create a topic branch in the parent and submodules'
+        'switch:Check out a branch in the parent and every submodule that has it'
+        'checkout:Move submodule worktrees to the commits recorded in the parent'
         'pull:Fetch and update submodules from upstream'
         'rm:Remove a submodule cleanly'
+        'sync:Reconcile .gitmodules, .git/config and submodule remotes'
+        'vendor:Replace a submodule with a vendored copy of its files'
+        'unvendor:Restore a vendored directory as a submodule'
         'diff:Show changes across submodules'
+        'adopt:Register existing nested clones as submodules'
+        'import:Add submodules from a repo, west or vcstool manifest'
+        'export:Render submodules and their recorded commits as a manifest'
         'foreach:Run a command in each submodule'
+        'log:Show commits from the parent and every submodule as one stream'
+        'grep:Search files in the parent and every submodule'
+        'history:List the parent commits that moved a submodule pointer'
+        'contains:Find the parent commits, branches and tags that include a submodule commit'
+        'tui:Browse submodules in an interactive dashboard'
+        'prompt:Print a one-line submodule summary for shell prompts'
+        'cache:Manage the status cache'
         'completions:Generate shell completions'
         'man:Generate or install a man page'
         'help:Print help'
@@ -39,10 +57,33 @@ _git-dom() {
         _describe 'submodule' names
     }
 
+    _git-dom_groups() {
+        local -a groups
+        groups=(${(f)"$(git config --file .gitmodules --get-regexp '^submodule\..*\.group$' 2>/dev/null | cut -d' ' -f2- | sort -u)"})
+        _describe 'group' groups
+    }
+
+    _git-dom_fields() {
+        local -a fields
+        fields=(name path url groups state branch detached head-commit head-short head-message head-author head-time recorded-commit staged-commit is-dirty ahead behind staged modified untracked parent-changed)
+        _describe 'field' fields
+    }
+
+    local -a selection
+    selection=(
+        '*'{-g,--group}'[Only operate on submodules in this group]:group:_git-dom_groups'
+        '--dirty[Only submodules with uncommitted changes]'
+        '--ahead[Only submodules with unpushed commits]'
+        '--behind[Only submodules whose upstream has new commits]'
+        '--detached[Only submodules with a detached HEAD]'
+        '--uninitialized[Only submodules that are not initialised]'
+    )
+
     local curcontext="$curcontext" state line
 
     _arguments -C \
         '--no-colour[Disable colour output]' \
+        '--no-cache[Bypass the status cache]' \
         '(-h --help)'{-h,--help}'[Print help]' \
         '(-V --version)'{-V,--version}'[Print version]' \
         '1:subcommand:->subcmd' \
@@ -54,56 +95,267 @@ _git-dom() {
             ;;
         args)
             case $line[1] in
-                ls|status)
+                ls)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '(-l --long --format --columns)'{-l,--long}'[Show full commit IDs, authors and dates]' \
+                        '(-l --long --columns)--format[Print each submodule using a template]:template:' \
+                        '--sort[Sort by a field]:field:_git-dom_fields' \
+                        '(-l --long --format)--columns[Print a table of these fields]:fields:_sequence _git-dom_fields' \
+                        '(-j --jobs)'{-j,--jobs}'[Number of submodules to inspect in parallel]:jobs:' \
+                        '(-w --watch)'{-w,--watch}'[Refresh whenever files change]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                status)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '(-l --long --porcelain)'{-l,--long}'[Show full commit IDs, authors and dates]' \
+                        '(-l --long)--porcelain[Print a stable format for scripts]' \
+                        '(-j --jobs)'{-j,--jobs}'[Number of submodules to inspect in parallel]:jobs:' \
+                        '(-w --watch --exit-code)'{-w,--watch}'[Refresh whenever files change]' \
+                        '(-w --watch)--exit-code[Exit non-zero when any submodule needs attention]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                attach)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
                         '(-h --help)'{-h,--help}'[Print help]' \
-                        '1::name:_git-dom_submodule_names'
+                        '*::name:_git-dom_submodule_names'
                     ;;
                 clone)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
                         '--no-commit[Skip commit prompt]' \
+                        '--from[Clone every entry in a manifest file]:file:_files' \
+                        '(-j --jobs)'{-j,--jobs}'[Number of parallel clones]:jobs:' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1::url:'
+                    ;;
+                init)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '(-j --jobs)'{-j,--jobs}'[Number of parallel clones]:jobs:' \
+                        '--recursive[Also initialise nested submodules]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                branch)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
                         '(-h --help)'{-h,--help}'[Print help]' \
-                        '1:url:'
+                        '1:branch:' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                switch)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:branch:'
+                    ;;
+                checkout)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '--head[Use the commits recorded in HEAD]' \
+                        '(-f --force)'{-f,--force}'[Discard uncommitted changes]' \
+                        '--skip-dirty[Leave submodules with uncommitted changes alone]' \
+                        '--install-hook[Install a post-checkout hook]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
                     ;;
                 pull)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
                         '--commit[Commit mode]:mode:(auto stage prompt)' \
                         '(-h --help)'{-h,--help}'[Print help]' \
-                        '1::name:_git-dom_submodule_names'
+                        '*::name:_git-dom_submodule_names'
                     ;;
                 rm)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
                         '--no-commit[Skip commit prompt]' \
                         '(-h --help)'{-h,--help}'[Print help]' \
-                        '1:name:_git-dom_submodule_names'
+                        '*:name:_git-dom_submodule_names'
+                    ;;
+                sync)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '--from[Source of truth]:source:(gitmodules config remote)' \
+                        '--check[Only report drift]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                vendor)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '--no-commit[Skip commit prompt]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*:name:_git-dom_submodule_names'
+                    ;;
+                unvendor)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '--no-commit[Skip commit prompt]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:path:_directories'
                     ;;
                 diff)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
                         '--full[Show full per-submodule diffs]' \
+                        '(-j --jobs)'{-j,--jobs}'[Number of submodules to inspect in parallel]:jobs:' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                adopt)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '--scan[Adopt every unregistered nested repository]' \
+                        '(-n --dry-run)'{-n,--dry-run}'[Only list what would be adopted]' \
+                        '--no-commit[Skip commit prompt]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1::path:_directories'
+                    ;;
+                import)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '--format[Manifest format]:format:(repo west vcs)' \
+                        '(-j --jobs)'{-j,--jobs}'[Number of parallel clones]:jobs:' \
+                        '--no-commit[Skip commit prompt]' \
                         '(-h --help)'{-h,--help}'[Print help]' \
-                        '1::name:_git-dom_submodule_names'
+                        '1:manifest:_files'
+                    ;;
+                export)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '--format[Manifest format]:format:(repo west vcs)' \
+                        '(-o --output)'{-o,--output}'[Write manifest to path]:path:_files' \
+                        '(-h --help)'{-h,--help}'[Print help]'
                     ;;
                 foreach)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '*'{-s,--select}'[Only run in matching submodules]:selector:_git-dom_submodule_names' \
                         '--parallel[Run in parallel]' \
                         '(-h --help)'{-h,--help}'[Print help]' \
                         '*:command:'
                     ;;
+                log)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '*'{-s,--select}'[Only include matching submodules]:selector:_git-dom_submodule_names' \
+                        '--since[Only commits more recent than this date]:date:' \
+                        '--until[Only commits older than this date]:date:' \
+                        '--author[Only commits whose author matches this pattern]:pattern:' \
+                        '(-n --max-count)'{-n,--max-count}'[Show at most this many commits]:count:' \
+                        '--json[Print the commits as JSON]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*:path:_files'
+                    ;;
+                grep)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '*'{-s,--select}'[Only search matching submodules]:selector:_git-dom_submodule_names' \
+                        '(-i --ignore-case)'{-i,--ignore-case}'[Ignore case differences]' \
+                        '(-l --files-with-matches)'{-l,--files-with-matches}'[Print only the names of matching files]' \
+                        '(-n --line-number)'{-n,--line-number}'[Prefix matching lines with their line numbers]' \
+                        '(--rev)--cached[Search the index instead of the working trees]' \
+                        '(--cached)--rev[Search the commits recorded at this parent revision]:revision:' \
+                        '--json[Print the matches as JSON]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:pattern:' \
+                        '*:pathspec:_files'
+                    ;;
+                history)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '(-n --max-count)'{-n,--max-count}'[Show at most this many commits]:count:' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:name:_git-dom_submodule_names'
+                    ;;
+                contains)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:name:_git-dom_submodule_names' \
+                        '2:commit:'
+                    ;;
+                tui)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '(-j --jobs)'{-j,--jobs}'[Number of submodules to inspect in parallel]:jobs:' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '*::name:_git-dom_submodule_names'
+                    ;;
+                prompt)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        "${selection[@]}" \
+                        '--format[Summary template]:format:' \
+                        '--timeout[Milliseconds to wait before giving up]:milliseconds:' \
+                        '--stale[Printed when the summary isn'"'"'t ready in time]:text:' \
+                        '(-h --help)'{-h,--help}'[Print help]'
+                    ;;
+                cache)
+                    _arguments \
+                        '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
+                        '(-h --help)'{-h,--help}'[Print help]' \
+                        '1:command:((clear\:"Delete the cached submodule status"))'
+                    ;;
                 completions)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
                         '(-h --help)'{-h,--help}'[Print help]' \
                         '1:shell:(bash zsh fish powershell elvish)'
                     ;;
                 man)
                     _arguments \
                         '--no-colour[Disable colour output]' \
+                        '--no-cache[Bypass the status cache]' \
                         '--install[Install to $XDG_DATA_HOME/man/man1 (or ~/.local/share/man/man1)]' \
                         '(-o --output)'{-o,--output}'[Write man page to path]:path:_files' \
                         '(-h --help)'{-h,--help}'[Print help]'
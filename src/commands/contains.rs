@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+
+use crate::commands::history;
+use crate::submodule;
+
+pub fn run(name: String, rev: String) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let sm = submodule::select_one(&repo, &name)?;
+    let sm_name = sm.name().unwrap_or("").to_string();
+    let path = sm.path().to_path_buf();
+    let sub_repo = sm.open().with_context(|| {
+        format!(
+            "{} is not initialised; run `git dom init {}` first",
+            sm_name, sm_name
+        )
+    })?;
+    let target = sub_repo
+        .revparse_single(&rev)
+        .and_then(|o| o.peel_to_commit())
+        .ok()
+        .with_context(|| format!("{} has no commit '{}'", sm_name, rev))?
+        .id();
+    let short = submodule::abbrev(&sub_repo, target);
+
+    let mut check = Contains {
+        sub_repo: &sub_repo,
+        target,
+        seen: HashMap::new(),
+        unknown: false,
+    };
+
+    // Oldest first, so the first gitlink that reaches the commit is the bump
+    // that pulled it in
+    let bumps = history::bumps(workdir, &path, None)?;
+    let first = bumps
+        .iter()
+        .rev()
+        .find(|bump| bump.new.is_some_and(|gitlink| check.includes(gitlink)));
+
+    match first {
+        Some(bump) => println!(
+            "{} {} first included by {} ({}, {}): {}",
+            sm_name.bold(),
+            short.yellow(),
+            submodule::abbrev(&repo, bump.commit).yellow(),
+            bump.date,
+            bump.author,
+            bump.subject
+        ),
+        None => println!(
+            "No commit on this branch records a {} commit that includes {}.",
+            sm_name.bold(),
+            short.yellow()
+        ),
+    }
+
+    let mut branches = Vec::new();
+    let mut tags = Vec::new();
+    for reference in repo.references()?.flatten() {
+        let Some(refname) = reference.name() else {
+            continue;
+        };
+        let list = if reference.is_tag() {
+            &mut tags
+        } else if reference.is_branch() || reference.is_remote() {
+            &mut branches
+        } else {
+            continue;
+        };
+        // Skip symbolic refs like origin/HEAD; their target is listed anyway
+        if reference.symbolic_target().is_some() {
+            continue;
+        }
+        let Ok(tree) = reference.peel_to_tree() else {
+            continue;
+        };
+        if let Some(gitlink) = submodule::recorded_in(&tree, &path)
+            && check.includes(gitlink)
+        {
+            list.push(reference.shorthand().unwrap_or(refname).to_string());
+        }
+    }
+    branches.sort();
+    tags.sort();
+
+    let list = |names: &[String]| {
+        if names.is_empty() {
+            "(none)".dimmed().to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+    println!("  {} {}", "branches:".dimmed(), list(&branches));
+    println!("  {} {}", "tags:    ".dimmed(), list(&tags));
+
+    if check.unknown {
+        eprintln!(
+            "{}",
+            format!(
+                "Some recorded {} commits aren't in the local clone; fetch it for a complete answer.",
+                sm_name
+            )
+            .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Answers "does this gitlink include the target?", remembering each answer
+/// since many refs record the same submodule commit.
+struct Contains<'r> {
+    sub_repo: &'r Repository,
+    target: Oid,
+    seen: HashMap<Oid, bool>,
+    /// Whether any gitlink couldn't be checked because it isn't fetched.
+    unknown: bool,
+}
+
+impl Contains<'_> {
+    fn includes(&mut self, gitlink: Oid) -> bool {
+        if let Some(&answer) = self.seen.get(&gitlink) {
+            return answer;
+        }
+        let answer = gitlink == self.target
+            || match self.sub_repo.graph_descendant_of(gitlink, self.target) {
+                Ok(answer) => answer,
+                Err(_) => {
+                    self.unknown = true;
+                    false
+                }
+            };
+        self.seen.insert(gitlink, answer);
+        answer
+    }
+}
@@ -3,14 +3,24 @@ use colored::Colorize;
 use git2::Repository;
 use std::process::Command;
 
-use crate::submodule;
+use crate::config::Config;
+use crate::submodule::{self, Detail, Filter};
 
-pub fn run(name: Option<String>, full: bool) -> Result<()> {
+pub fn run(filter: Filter, full: bool, jobs: Option<usize>) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
     let workdir = repo
         .workdir()
         .context("Bare repositories are not supported")?;
-    let submodules = submodule::discover(&repo, name.as_deref())?;
+    let jobs = jobs.map_or_else(|| Config::load(&repo).map(|c| c.jobs), Ok)?;
+    let submodules = submodule::discover(
+        &repo,
+        &filter,
+        jobs,
+        Detail {
+            upstream: false,
+            ..Detail::all()
+        },
+    )?;
 
     if submodules.is_empty() {
         println!("No submodules found.");
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use git2::Repository;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::manifest::{ManifestFormat, Project};
+use crate::submodule::{self, Filter};
+
+pub fn run(filter: Filter, format: ManifestFormat, output: Option<PathBuf>) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let projects = projects(&repo, &filter)?;
+
+    let rendered = format.render(&projects)?;
+
+    match output {
+        Some(path) => std::fs::write(&path, rendered)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => std::io::stdout()
+            .write_all(rendered.as_bytes())
+            .context("Failed to write manifest to stdout")?,
+    }
+
+    Ok(())
+}
+
+/// The selected submodules as manifest projects.
+fn projects(repo: &Repository, filter: &Filter) -> Result<Vec<Project>> {
+    Ok(submodule::select(repo, filter)?
+        .iter()
+        .map(|sm| Project {
+            name: sm.name().unwrap_or("").to_string(),
+            path: sm.path().to_string_lossy().into_owned(),
+            // Manifests have no notion of the parent's remote
+            url: submodule::resolve_url(repo, sm.url().unwrap_or("")),
+            // Prefer the staged gitlink so a pending bump is exported as-is
+            revision: sm.index_id().or(sm.head_id()).map(|oid| oid.to_string()),
+            branch: sm.branch().map(|b| b.to_string()),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::projects;
+    use crate::manifest::ManifestFormat;
+    use crate::submodule::Filter;
+
+    #[test]
+    fn exports_relative_urls_resolved_against_origin() {
+        let dir = std::env::temp_dir().join(format!("git-dom-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = git2::Repository::init(&dir).unwrap();
+        repo.remote("origin", "https://example.com/org/parent.git")
+            .unwrap();
+        std::fs::write(
+            dir.join(".gitmodules"),
+            "[submodule \"lib\"]\n\tpath = libs/lib\n\turl = ../lib.git\n",
+        )
+        .unwrap();
+
+        let exported = projects(&repo, &Filter::new(Vec::new(), Vec::new(), Vec::new()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let exported = exported.unwrap();
+        assert_eq!(exported[0].url, "https://example.com/org/lib.git");
+
+        let xml = ManifestFormat::Repo.render(&exported).unwrap();
+        let imported = ManifestFormat::Repo.parse(&xml).unwrap();
+        assert_eq!(imported[0].url, "https://example.com/org/lib.git");
+        assert_eq!(imported[0].path, "libs/lib");
+    }
+}
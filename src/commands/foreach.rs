@@ -1,16 +1,27 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
 use git2::Repository;
-use std::process::Command;
-
-use crate::submodule;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::config::Config;
+use crate::parallel;
+use crate::submodule::{self, Detail, Filter};
+
+/// A command to run in one repository.
+pub(crate) struct Job {
+    pub dir: PathBuf,
+    pub command: Vec<String>,
+}
 
-pub fn run(command: Vec<String>, parallel: bool) -> Result<()> {
+pub fn run(filter: Filter, command: Vec<String>, parallel: bool) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
     let workdir = repo
         .workdir()
         .context("Bare repositories are not supported")?;
-    let submodules = submodule::discover(&repo, None)?;
+    let submodules =
+        submodule::discover(&repo, &filter, Config::load(&repo)?.jobs, Detail::default())?;
 
     if submodules.is_empty() {
         println!("No submodules found.");
@@ -33,15 +44,12 @@ pub fn run(command: Vec<String>, parallel: bool) -> Result<()> {
 fn run_sequential(
     submodules: &[submodule::SubmoduleInfo],
     command: &[String],
-    workdir: &std::path::Path,
+    workdir: &Path,
 ) -> Result<()> {
     for sm in submodules {
         println!("{}", format!("─── {} ───", sm.name).bold());
 
-        let output = Command::new(&command[0])
-            .args(&command[1..])
-            .current_dir(workdir.join(&sm.path))
-            .output()
+        let output = output(&workdir.join(&sm.path), command)
             .with_context(|| format!("Failed to run command in {}", sm.name))?;
 
         print!("{}", String::from_utf8_lossy(&output.stdout));
@@ -65,31 +73,21 @@ fn run_sequential(
 fn run_parallel(
     submodules: &[submodule::SubmoduleInfo],
     command: &[String],
-    workdir: &std::path::Path,
+    workdir: &Path,
 ) -> Result<()> {
-    use std::thread;
-
-    let handles: Vec<_> = submodules
+    let jobs: Vec<Job> = submodules
         .iter()
-        .map(|sm| {
-            let cmd = command.to_vec();
-            let dir = workdir.join(&sm.path);
-            let name = sm.name.clone();
-
-            thread::spawn(move || {
-                let output = Command::new(&cmd[0])
-                    .args(&cmd[1..])
-                    .current_dir(&dir)
-                    .output();
-
-                (name, output)
-            })
+        .map(|sm| Job {
+            dir: workdir.join(&sm.path),
+            command: command.to_vec(),
         })
         .collect();
 
-    for handle in handles {
-        let (name, result) = handle.join().unwrap();
+    // Everything at once; the commands are usually waiting on the network
+    let results = run_all(&jobs, jobs.len());
 
+    for (sm, result) in submodules.iter().zip(results) {
+        let name = &sm.name;
         println!("{}", format!("─── {} ───", name).bold());
 
         match result {
@@ -116,3 +114,15 @@ fn run_parallel(
 
     Ok(())
 }
+
+/// Run every job on up to `threads` threads, returning the outputs in order.
+pub(crate) fn run_all(jobs: &[Job], threads: usize) -> Vec<io::Result<Output>> {
+    parallel::map(jobs, threads, |job| output(&job.dir, &job.command))
+}
+
+fn output(dir: &Path, command: &[String]) -> io::Result<Output> {
+    Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(dir)
+        .output()
+}
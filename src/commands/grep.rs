@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::commands::foreach::{self, Job};
+use crate::commands::log::{LITERAL, paths_within, pathspec_arg, relative_to};
+use crate::config::Config;
+use crate::submodule::{self, Detail, Filter};
+
+/// One matching line, or one matching file with `-l`.
+#[derive(Serialize)]
+struct Hit {
+    /// Submodule name, or the parent's directory name.
+    repo: String,
+    /// Submodule path, or `.` for the parent.
+    path: String,
+    /// The file, relative to the top of the parent.
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip)]
+    binary: bool,
+}
+
+/// Options passed through to every `git grep`.
+pub struct Options {
+    pub ignore_case: bool,
+    pub files_with_matches: bool,
+    pub line_number: bool,
+    pub cached: bool,
+    pub rev: Option<String>,
+}
+
+pub fn run(
+    filter: Filter,
+    pattern: String,
+    pathspecs: Vec<String>,
+    options: Options,
+    json: bool,
+) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+    let jobs = Config::load(&repo)?.jobs;
+
+    // Paths narrow the search to the repositories they reach, as in `git dom
+    // log`; patterns like '*.rs' are handed to every repository as they are.
+    // `:(literal)` marks a path whose name happens to contain `*?[`.
+    let (patterns, paths): (Vec<_>, Vec<_>) = pathspecs
+        .into_iter()
+        .partition(|p| !p.starts_with(LITERAL) && p.contains(['*', '?', '[']));
+    let paths = paths
+        .iter()
+        .map(|p| relative_to(workdir, Path::new(p.trim_start_matches(LITERAL))))
+        .collect::<Result<Vec<_>>>()?;
+
+    // With --rev each submodule is searched at the commit the parent records
+    // for it there, rather than in its working tree
+    let tree = match options.rev {
+        Some(ref rev) => Some(
+            repo.revparse_single(rev)
+                .and_then(|o| o.peel_to_tree())
+                .ok()
+                .with_context(|| format!("Unknown revision '{}'", rev))?,
+        ),
+        None => None,
+    };
+
+    let parent = workdir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| ".".to_string());
+    let mut targets = vec![(
+        parent,
+        PathBuf::from("."),
+        options.rev.clone(),
+        paths.clone(),
+    )];
+    for sm in submodule::discover(&repo, &filter, jobs, Detail::default())? {
+        if sm.head_commit.is_none() {
+            continue;
+        }
+        let Some(inner) = paths_within(&paths, &sm.path) else {
+            continue;
+        };
+        let rev = match tree {
+            Some(ref tree) => match submodule::recorded_in(tree, &sm.path) {
+                Some(oid) => Some(oid.to_string()),
+                // Not a submodule yet at that revision
+                None => continue,
+            },
+            None => None,
+        };
+        targets.push((sm.name, sm.path, rev, inner));
+    }
+
+    let commands: Vec<Job> = targets
+        .iter()
+        .map(|(_, path, rev, paths)| Job {
+            dir: workdir.join(path),
+            command: command(&pattern, &options, json, rev.as_deref(), paths, &patterns),
+        })
+        .collect();
+    let results = foreach::run_all(&commands, jobs);
+
+    let mut hits = Vec::new();
+    let mut failed = false;
+    for ((name, path, rev, _), result) in targets.iter().zip(results) {
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("{} {}: {}", "✗".red().bold(), name, e);
+                failed = true;
+                continue;
+            }
+        };
+        // git grep exits 1 when nothing matches
+        match output.status.code() {
+            Some(0) => {}
+            Some(1) => continue,
+            _ => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                eprintln!("{} {}: {}", "✗".red().bold(), name, stderr.trim());
+                failed = true;
+                continue;
+            }
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for m in parse(&stdout, rev.as_deref(), options.files_with_matches) {
+            let file = if path == Path::new(".") {
+                m.file
+            } else {
+                path.join(&m.file).display().to_string()
+            };
+            hits.push(Hit {
+                repo: name.clone(),
+                path: path.display().to_string(),
+                file,
+                line: m.line,
+                text: m.text,
+                binary: m.binary,
+            });
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+    } else {
+        print_hits(&hits);
+    }
+
+    // Exit like `git grep`: 1 for no match anywhere, 2 if a search failed
+    if failed {
+        std::process::exit(2);
+    }
+    if hits.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_hits(hits: &[Hit]) {
+    for hit in hits {
+        if hit.binary {
+            println!("Binary file {} matches", hit.file.magenta());
+            continue;
+        }
+        let mut line = hit.file.magenta().to_string();
+        if let Some(n) = hit.line {
+            line.push_str(&format!("{}{}", ":".cyan(), n.to_string().green()));
+        }
+        if let Some(ref text) = hit.text {
+            line.push_str(&format!("{}{}", ":".cyan(), text));
+        }
+        println!("{}", line);
+    }
+}
+
+fn command(
+    pattern: &str,
+    options: &Options,
+    json: bool,
+    rev: Option<&str>,
+    paths: &[PathBuf],
+    patterns: &[String],
+) -> Vec<String> {
+    // NUL after each file name keeps names with colons in them unambiguous
+    let mut command = vec![
+        "git".to_string(),
+        "grep".to_string(),
+        "--null".to_string(),
+        "--no-color".to_string(),
+    ];
+    if options.ignore_case {
+        command.push("--ignore-case".to_string());
+    }
+    if options.files_with_matches {
+        command.push("--files-with-matches".to_string());
+    } else if options.line_number || json {
+        command.push("--line-number".to_string());
+    }
+    if options.cached {
+        command.push("--cached".to_string());
+    }
+    command.push("-e".to_string());
+    command.push(pattern.to_string());
+    if let Some(rev) = rev {
+        command.push(rev.to_string());
+    }
+    command.push("--".to_string());
+    command.extend(paths.iter().map(|p| pathspec_arg(p, true)));
+    command.extend(patterns.iter().cloned());
+    command
+}
+
+/// One match as `git grep --null` reports it.
+struct Match {
+    file: String,
+    line: Option<usize>,
+    text: Option<String>,
+    binary: bool,
+}
+
+/// Parse `git grep --null` output. Files searched at `rev` come back as
+/// `<rev>:<file>`, so that prefix is dropped.
+fn parse(output: &str, rev: Option<&str>, files_only: bool) -> Vec<Match> {
+    let prefix = rev.map(|r| format!("{}:", r)).unwrap_or_default();
+    let file = |f: &str| f.strip_prefix(prefix.as_str()).unwrap_or(f).to_string();
+    let matched = |f: &str| Match {
+        file: file(f),
+        line: None,
+        text: None,
+        binary: false,
+    };
+
+    if files_only {
+        return output
+            .split('\0')
+            .filter(|f| !f.is_empty())
+            .map(matched)
+            .collect();
+    }
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let Some((name, rest)) = line.split_once('\0') else {
+                // Binary files get a message in place of their lines
+                let name = line
+                    .strip_prefix("Binary file ")?
+                    .strip_suffix(" matches")?;
+                return Some(Match {
+                    binary: true,
+                    ..matched(name)
+                });
+            };
+            let (line, text) = match rest.split_once('\0') {
+                Some((n, text)) => (n.parse().ok(), text),
+                None => (None, rest),
+            };
+            Some(Match {
+                line,
+                text: Some(text.to_string()),
+                ..matched(name)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_null_separated_matches() {
+        let output = "HEAD:src/a.rs\x0012\x00let x = 1;\nBinary file HEAD:b.bin matches\n";
+        let matches = parse(output, Some("HEAD"), false);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].file, "src/a.rs");
+        assert_eq!(matches[0].line, Some(12));
+        assert_eq!(matches[0].text.as_deref(), Some("let x = 1;"));
+        assert!(matches[1].binary && matches[1].file == "b.bin");
+
+        let matches = parse("a.rs\x00b: c.rs\x00", None, true);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].file, "b: c.rs");
+    }
+}
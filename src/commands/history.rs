@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::{Oid, Repository};
+use std::path::Path;
+
+use crate::git;
+use crate::submodule;
+
+/// One parent commit that moved the gitlink.
+pub(crate) struct Bump {
+    pub commit: Oid,
+    pub date: String,
+    pub author: String,
+    pub subject: String,
+    pub old: Option<Oid>,
+    pub new: Option<Oid>,
+}
+
+pub fn run(name: String, max_count: Option<usize>) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let sm = submodule::select_one(&repo, &name)?;
+    let sm_name = sm.name().unwrap_or("").to_string();
+    let path = sm.path().to_path_buf();
+
+    let bumps = bumps(workdir, &path, max_count)?;
+
+    if bumps.is_empty() {
+        println!("No commits in this branch changed {}.", sm_name);
+        return Ok(());
+    }
+
+    // Counting commits needs the submodule's own history
+    let sub_repo = sm.open().ok();
+    let short = |oid: Option<Oid>| match (oid, &sub_repo) {
+        (Some(oid), Some(r)) => submodule::abbrev(r, oid),
+        (Some(oid), None) => format!("{:.7}", oid),
+        (None, _) => "(none)".to_string(),
+    };
+
+    for bump in &bumps {
+        let change = format!("{} → {}", short(bump.old), short(bump.new));
+        // Padded before colouring so the escape codes don't upset the width
+        let pad = |s: &str| format!("{:<8}", s);
+        let count = match (bump.old, bump.new, &sub_repo) {
+            (None, Some(_), _) => pad("added").green(),
+            (Some(_), None, _) => pad("removed").red(),
+            (Some(old), Some(new), Some(r)) => match r.graph_ahead_behind(new, old) {
+                Ok((ahead, 0)) => pad(&format!("+{}", ahead)).green(),
+                Ok((0, behind)) => pad(&format!("-{}", behind)).red(),
+                Ok((ahead, behind)) => pad(&format!("+{} -{}", ahead, behind)).yellow(),
+                // The commits aren't in the local clone; a fetch would tell
+                Err(_) => pad("?").dimmed(),
+            },
+            _ => pad("?").dimmed(),
+        };
+
+        println!(
+            "{}  {}  {}  {}  {}  {}",
+            bump.date.dimmed(),
+            submodule::abbrev(&repo, bump.commit).yellow(),
+            change,
+            count,
+            bump.subject,
+            format!("({})", bump.author).dimmed()
+        );
+    }
+
+    if sub_repo.is_none() {
+        eprintln!(
+            "{}",
+            format!(
+                "{} is not initialised, so commits can't be counted.",
+                sm_name
+            )
+            .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// The commits on the current branch that changed the gitlink at `path`,
+/// newest first.
+pub(crate) fn bumps(workdir: &Path, path: &Path, max_count: Option<usize>) -> Result<Vec<Bump>> {
+    // git log's history simplification skips merges that didn't change the
+    // gitlink themselves, and --raw gives the old and new commit for each bump.
+    // A merge that settled on a commit of its own is diffed against the branch
+    // it was merged into, since --raw shows nothing for merges by default.
+    let mut args = vec![
+        "log".to_string(),
+        "--format=%x1e%H%x1f%ad%x1f%an%x1f%s".to_string(),
+        "--date=format:%Y-%m-%d %H:%M".to_string(),
+        "--raw".to_string(),
+        "--diff-merges=first-parent".to_string(),
+        "--no-abbrev".to_string(),
+        "--no-renames".to_string(),
+    ];
+    if let Some(n) = max_count {
+        args.push(format!("--max-count={}", n));
+    }
+    args.push("--".to_string());
+    args.push(path.display().to_string());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Ok(parse(&git::output(workdir, &args)?, path))
+}
+
+/// Parse `git log --raw` output into the changes it records to the gitlink at
+/// `path`. Changes beneath `path`, from when it was an ordinary directory,
+/// aren't bumps.
+fn parse(output: &str, path: &Path) -> Vec<Bump> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.split('\x1f');
+            let commit = Oid::from_str(fields.next()?).ok()?;
+            let date = fields.next()?.to_string();
+            let author = fields.next()?.to_string();
+            let subject = fields.next()?.to_string();
+
+            // `:<old mode> <new mode> <old oid> <new oid> <status>\t<path>`
+            let (raw, _) = lines
+                .filter_map(|l| l.strip_prefix(':')?.split_once('\t'))
+                .find(|(_, p)| Path::new(p) == path)?;
+            let mut parts = raw.split_whitespace();
+            let (old_mode, new_mode) = (parts.next()?, parts.next()?);
+            let (old, new) = (parts.next()?, parts.next()?);
+            if old_mode != "160000" && new_mode != "160000" {
+                return None;
+            }
+            let gitlink = |mode: &str, oid: &str| {
+                (mode == "160000")
+                    .then(|| Oid::from_str(oid).ok())
+                    .flatten()
+            };
+
+            Some(Bump {
+                commit,
+                date,
+                author,
+                subject,
+                old: gitlink(old_mode, old),
+                new: gitlink(new_mode, new),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use std::path::Path;
+
+    #[test]
+    fn reads_gitlink_changes_from_raw_log() {
+        let output = "\x1eaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\x1f2024-01-02 10:00\x1fAlice\x1fBump foo\n\n\
+            :160000 160000 1111111111111111111111111111111111111111 \
+            2222222222222222222222222222222222222222 M\tlibs/foo\n\
+            \x1ebbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\x1f2024-01-01 09:00\x1fBob\x1fAdd foo\n\n\
+            :000000 160000 0000000000000000000000000000000000000000 \
+            1111111111111111111111111111111111111111 A\tlibs/foo\n";
+
+        let bumps = parse(output, Path::new("libs/foo"));
+        assert_eq!(bumps.len(), 2);
+        assert_eq!(
+            bumps[0].commit.to_string(),
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+        );
+        assert_eq!(bumps[0].author, "Alice");
+        assert!(bumps[0].old.is_some() && bumps[0].new.is_some());
+        assert!(bumps[1].old.is_none() && bumps[1].new.is_some());
+    }
+
+    #[test]
+    fn skips_files_that_were_never_the_gitlink() {
+        let output = "\x1eaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\x1f2024-01-02 10:00\x1fAlice\x1fMove foo\n\n\
+            :100644 000000 3333333333333333333333333333333333333333 \
+            0000000000000000000000000000000000000000 D\tlibs/foo/README\n\
+            :000000 160000 0000000000000000000000000000000000000000 \
+            1111111111111111111111111111111111111111 A\tlibs/foo\n\
+            \x1ebbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\x1f2024-01-01 09:00\x1fBob\x1fVendor foo\n\n\
+            :000000 100644 0000000000000000000000000000000000000000 \
+            3333333333333333333333333333333333333333 A\tlibs/foo/README\n";
+
+        let bumps = parse(output, Path::new("libs/foo"));
+        assert_eq!(bumps.len(), 1);
+        assert!(bumps[0].old.is_none());
+        assert_eq!(
+            bumps[0].new.unwrap().to_string(),
+            "1111111111111111111111111111111111111111"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use std::io::Read;
+
+use crate::commands::clone::{self, PlannedClone};
+use crate::manifest::ManifestFormat;
+
+pub fn run(
+    manifest: String,
+    format: Option<ManifestFormat>,
+    jobs: usize,
+    no_commit: bool,
+) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let content = if manifest == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("Failed to read manifest from stdin")?;
+        buf
+    } else {
+        std::fs::read_to_string(&manifest)
+            .with_context(|| format!("Failed to read {}", manifest))?
+    };
+
+    let format = format
+        .or_else(|| ManifestFormat::detect(&manifest, &content))
+        .context("Could not detect the manifest format; pass --format")?;
+    let projects = format.parse(&content)?;
+
+    let existing: Vec<_> = repo
+        .submodules()
+        .context("Failed to read submodules")?
+        .iter()
+        .map(|sm| sm.path().to_path_buf())
+        .collect();
+
+    let mut planned = Vec::new();
+    for p in projects {
+        if existing.iter().any(|e| e.as_os_str() == p.path.as_str()) {
+            println!(
+                "  {} {} is already a submodule, skipping",
+                "·".dimmed(),
+                p.path
+            );
+            continue;
+        }
+
+        planned.push(PlannedClone {
+            git_url: p.url,
+            sub_path: p.path,
+            branch: p.branch,
+            revision: p.revision,
+            groups: Vec::new(),
+        });
+    }
+
+    if planned.is_empty() {
+        println!(
+            "{}",
+            "All manifest projects are already submodules.".green()
+        );
+        return Ok(());
+    }
+
+    let batch = clone::add_batch(workdir, &planned, jobs)?;
+
+    let source = if manifest == "-" { "stdin" } else { &manifest };
+    let msg = format!(
+        "Import submodules from {}: {}",
+        source,
+        batch.added.join(", ")
+    );
+    clone::commit_batch(workdir, &msg, &batch.added, no_commit, manifest != "-")?;
+    batch.check()
+}
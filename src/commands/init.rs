@@ -0,0 +1,200 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::{BranchType, Repository};
+use std::io::Write;
+
+use crate::commands::attach::{Attach, remote_default_branch};
+use crate::git;
+use crate::submodule::{self, Detail, Filter, SubmoduleInfo, SubmoduleState};
+
+pub fn run(filter: Filter, jobs: usize, recursive: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let selected = submodule::discover(&repo, &filter, jobs, Detail::default())?;
+    if selected.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    let max_name = selected.iter().map(|sm| sm.name.len()).max().unwrap_or(0);
+
+    // Initialised submodules are left alone, since updating them would move
+    // them off whatever branch they're on
+    let (pending, rest): (Vec<_>, Vec<_>) = selected
+        .iter()
+        .partition(|sm| sm.state == SubmoduleState::Uninitialised);
+
+    let mut failed = 0;
+    for sm in &rest {
+        match sm.state {
+            SubmoduleState::Initialised | SubmoduleState::UrlMismatch => println!(
+                "  {} {:<width$}  {}",
+                "·".dimmed(),
+                sm.name.bold(),
+                "already initialised".dimmed(),
+                width = max_name
+            ),
+            state => {
+                failed += 1;
+                eprintln!(
+                    "  {} {:<width$}  {}",
+                    "✗".red().bold(),
+                    sm.name.bold(),
+                    state.label().red(),
+                    width = max_name
+                );
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        println!(
+            "{} {} submodule(s) with {} job(s)",
+            "Initialising".bold(),
+            pending.len(),
+            jobs
+        );
+
+        // git clones submodules in parallel itself with --jobs
+        let jobs_arg = jobs.to_string();
+        let mut args = vec!["submodule", "update", "--init", "--jobs", &jobs_arg];
+        if recursive {
+            args.push("--recursive");
+        }
+        args.push("--");
+        args.extend(pending.iter().filter_map(|sm| sm.path.to_str()));
+        git::run(workdir, &args)?;
+    }
+
+    for info in &pending {
+        let sm = repo
+            .find_submodule(&info.name)
+            .with_context(|| format!("Failed to read submodule '{}'", info.name))?;
+        let sub_repo = sm
+            .open()
+            .with_context(|| format!("Failed to open submodule {}", info.name))?;
+
+        let outcome = attach_at_tip(&sub_repo, sm.branch())?;
+        match outcome {
+            Attach::Attached(branch) | Attach::FastForwarded(branch) => println!(
+                "  {} {:<width$}  {}",
+                "✓".green().bold(),
+                info.name.bold(),
+                branch.cyan(),
+                width = max_name
+            ),
+            Attach::Detached { commit, reason } => println!(
+                "  {} {:<width$}  {} {}",
+                "·".dimmed(),
+                info.name.bold(),
+                format!("detached at {:.7}", commit).yellow(),
+                format!("({})", reason).dimmed(),
+                width = max_name
+            ),
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} submodule(s) could not be initialised", failed);
+    }
+
+    Ok(())
+}
+
+/// Point out uninitialised submodules after listing them, offering to
+/// initialise them when running interactively. Output piped elsewhere is
+/// left alone, since something else is reading it.
+pub(crate) fn offer(submodules: &[SubmoduleInfo], jobs: usize) -> Result<()> {
+    if !atty::is(atty::Stream::Stdout) {
+        return Ok(());
+    }
+
+    let names: Vec<String> = submodules
+        .iter()
+        .filter(|sm| sm.state == SubmoduleState::Uninitialised)
+        .map(|sm| sm.name.clone())
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    if !atty::is(atty::Stream::Stdin) {
+        eprintln!(
+            "{}",
+            format!(
+                "{} submodule(s) not initialised; run `git dom init` to clone them.",
+                names.len()
+            )
+            .dimmed()
+        );
+        return Ok(());
+    }
+
+    println!();
+    print!(
+        "{} submodule(s) not initialised. Initialise now? [Y/n] ",
+        names.len()
+    );
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("y") {
+        run(Filter::names(names), jobs, false)?;
+    }
+
+    Ok(())
+}
+
+/// Attach a detached HEAD to a local branch when it sits exactly at the tip
+/// of the tracked branch, creating the local branch if needed.
+fn attach_at_tip(sub_repo: &Repository, configured: Option<&str>) -> Result<Attach> {
+    let head = sub_repo.head().context("Submodule has no HEAD")?;
+    let oid = head.target().context("Submodule HEAD is not a commit")?;
+
+    if head.is_branch() {
+        return Ok(Attach::Attached(
+            head.shorthand().unwrap_or_default().to_string(),
+        ));
+    }
+
+    let detached = |reason: String| {
+        Ok(Attach::Detached {
+            commit: oid,
+            reason,
+        })
+    };
+
+    let Some(branch) = configured
+        .map(|b| b.to_string())
+        .or_else(|| remote_default_branch(sub_repo))
+    else {
+        return detached("no tracked branch".to_string());
+    };
+
+    let upstream = format!("origin/{}", branch);
+    let upstream_tip = sub_repo
+        .find_reference(&format!("refs/remotes/{}", upstream))
+        .ok()
+        .and_then(|r| r.target());
+    if upstream_tip != Some(oid) {
+        return detached(format!("recorded commit is not the tip of {}", upstream));
+    }
+
+    match sub_repo.find_branch(&branch, BranchType::Local) {
+        Ok(local) if local.get().target() == Some(oid) => {}
+        Ok(_) => return detached(format!("local {} has diverged", branch)),
+        Err(_) => {
+            let commit = sub_repo.find_commit(oid)?;
+            let mut local = sub_repo.branch(&branch, &commit, false)?;
+            local.set_upstream(Some(&upstream))?;
+        }
+    }
+
+    sub_repo.set_head(&format!("refs/heads/{}", branch))?;
+    Ok(Attach::Attached(branch))
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use colored::Colorize;
+use git2::Repository;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git;
+use crate::parallel;
+use crate::submodule::{self, Detail, Filter};
+
+/// One commit in the merged stream.
+#[derive(Serialize)]
+struct Entry {
+    /// Submodule name, or the parent's directory name.
+    repo: String,
+    /// Submodule path, or `.` for the parent.
+    path: String,
+    commit: String,
+    #[serde(skip)]
+    short: String,
+    author: String,
+    email: String,
+    /// Committer date, RFC 3339 in local time.
+    date: String,
+    #[serde(skip)]
+    timestamp: i64,
+    subject: String,
+}
+
+/// Options passed through to every `git log`.
+pub struct Options {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub author: Option<String>,
+    pub max_count: Option<usize>,
+}
+
+pub fn run(filter: Filter, options: Options, paths: Vec<PathBuf>, json: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+    let jobs = Config::load(&repo)?.jobs;
+
+    let paths = paths
+        .iter()
+        .map(|p| relative_to(workdir, p))
+        .collect::<Result<Vec<_>>>()?;
+
+    // The parent, then every checked-out submodule the paths reach into
+    let parent = workdir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| ".".to_string());
+    let mut repos = vec![(parent, PathBuf::from("."), paths.clone())];
+    for sm in submodule::discover(&repo, &filter, jobs, Detail::default())? {
+        if sm.head_commit.is_none() {
+            continue;
+        }
+        if let Some(inner) = paths_within(&paths, &sm.path) {
+            repos.push((sm.name, sm.path, inner));
+        }
+    }
+
+    let logs = parallel::map(&repos, jobs, |(name, path, paths)| {
+        log(&workdir.join(path), name, path, paths, &options)
+    });
+    let mut entries = Vec::new();
+    for log in logs {
+        entries.extend(log?);
+    }
+
+    // Newest first, as git log shows it; the sort is stable so each repo
+    // keeps its own order for commits made in the same second
+    entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    if let Some(n) = options.max_count {
+        entries.truncate(n);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let width = entries.iter().map(|e| e.repo.len()).max().unwrap_or(0);
+    for e in &entries {
+        let date = Local
+            .timestamp_opt(e.timestamp, 0)
+            .single()
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!(
+            "{}  {:<width$}  {}  {}  {}",
+            date.dimmed(),
+            e.repo.cyan(),
+            e.short.yellow(),
+            e.subject,
+            format!("({})", e.author).dimmed(),
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+fn log(
+    dir: &Path,
+    name: &str,
+    path: &Path,
+    paths: &[PathBuf],
+    options: &Options,
+) -> Result<Vec<Entry>> {
+    // Fields and records are split on ASCII unit and record separators, which
+    // can't appear in a subject line
+    let mut args = vec![
+        "log".to_string(),
+        "--format=%H%x1f%h%x1f%ct%x1f%an%x1f%ae%x1f%s%x1e".to_string(),
+    ];
+    if let Some(ref since) = options.since {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(ref until) = options.until {
+        args.push(format!("--until={}", until));
+    }
+    if let Some(ref author) = options.author {
+        args.push(format!("--author={}", author));
+    }
+    if let Some(n) = options.max_count {
+        args.push(format!("--max-count={}", n));
+    }
+    args.push("--".to_string());
+    args.extend(paths.iter().map(|p| pathspec_arg(p, false)));
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output =
+        git::output(dir, &args).with_context(|| format!("Failed to read the log of {}", name))?;
+
+    Ok(output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            let commit = fields.next().filter(|c| !c.is_empty())?.to_string();
+            let short = fields.next()?.to_string();
+            let timestamp = fields.next()?.parse().ok()?;
+            Some(Entry {
+                repo: name.to_string(),
+                path: path.display().to_string(),
+                commit,
+                short,
+                author: fields.next()?.to_string(),
+                email: fields.next()?.to_string(),
+                date: Local.timestamp_opt(timestamp, 0).single()?.to_rfc3339(),
+                timestamp,
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// `path` as given on the command line, relative to the top of the parent.
+pub(crate) fn relative_to(workdir: &Path, path: &Path) -> Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let absolute = cwd.join(path);
+    // Compare canonical forms where possible so symlinked checkouts still match
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    absolute
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .ok()
+        .with_context(|| format!("{} is outside the repository", path.display()))
+}
+
+/// The parts of `paths` inside the submodule at `sm_path`, relative to it.
+/// `None` means none of them reach the submodule; an empty list means all of
+/// it, both when no paths were given and when one contains the submodule.
+pub(crate) fn paths_within(paths: &[PathBuf], sm_path: &Path) -> Option<Vec<PathBuf>> {
+    if paths.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut inner = Vec::new();
+    for path in paths {
+        if sm_path.starts_with(path) {
+            return Some(Vec::new());
+        }
+        if let Ok(rest) = path.strip_prefix(sm_path) {
+            inner.push(rest.to_path_buf());
+        }
+    }
+    (!inner.is_empty()).then_some(inner)
+}
+
+/// Pathspec magic that stops git reading `*?[` in a path as a pattern.
+pub(crate) const LITERAL: &str = ":(literal)";
+
+/// `path` as a pathspec argument, marked [`LITERAL`] when `literal` so glob
+/// characters in it match themselves.
+pub(crate) fn pathspec_arg(path: &Path, literal: bool) -> String {
+    let magic = if literal { LITERAL } else { "" };
+    // The top of the repository comes back as an empty path, which git rejects
+    if path.as_os_str().is_empty() {
+        format!("{}.", magic)
+    } else {
+        format!("{}{}", magic, path.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::paths_within;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn narrows_paths_to_the_submodules_they_reach() {
+        let sm = Path::new("libs/foo");
+        let paths = |ps: &[&str]| ps.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        assert_eq!(paths_within(&[], sm), Some(vec![]));
+        assert_eq!(paths_within(&paths(&["libs"]), sm), Some(vec![]));
+        assert_eq!(
+            paths_within(&paths(&["libs/foo/src", "docs"]), sm),
+            Some(paths(&["src"]))
+        );
+        assert_eq!(paths_within(&paths(&["libs/foobar"]), sm), None);
+    }
+}
@@ -2,39 +2,114 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use git2::Repository;
 
-use crate::submodule;
+use crate::commands::init;
+use crate::config::Config;
+use crate::submodule::{self, Filter, SubmoduleState};
+use crate::template::{self, Template};
+use crate::watch;
 
-pub fn run(name: Option<String>) -> Result<()> {
+pub fn run(
+    filter: Filter,
+    long: bool,
+    format: Option<String>,
+    sort: Option<String>,
+    columns: Vec<String>,
+    jobs: Option<usize>,
+    watch: bool,
+) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
-    let submodules = submodule::discover(&repo, name.as_deref())?;
-
-    if submodules.is_empty() {
-        println!("No submodules found.");
-        return Ok(());
+    // Parse up front so a bad template or field fails before any submodule is opened
+    let format = format.as_deref().map(Template::parse).transpose()?;
+    if let Some(ref key) = sort {
+        template::check_sort_key(key)?;
     }
+    template::check_columns(&columns)?;
+    let jobs = jobs.map_or_else(|| Config::load(&repo).map(|c| c.jobs), Ok)?;
 
-    // Calculate column widths
-    let max_name = submodules.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let list = |offer: bool| -> Result<()> {
+        // Only gather what will be shown; the default view needs just the dirty bit
+        let shown: Vec<&str> = match format {
+            Some(ref format) => format.fields().collect(),
+            None if !columns.is_empty() => columns.iter().map(|c| c.as_str()).collect(),
+            None => vec!["is-dirty"],
+        };
+        let detail = template::detail(shown.into_iter().chain(sort.as_deref()));
+        let mut submodules = submodule::discover(&repo, &filter, jobs, detail)?;
 
-    for sm in &submodules {
-        let branch = sm.branch.as_deref().unwrap_or("(none)");
-        let commit = sm.head_commit.as_deref().unwrap_or("-------");
+        if let Some(ref key) = sort {
+            template::sort(&mut submodules, key)?;
+        }
 
-        let status = if sm.is_dirty {
-            "dirty".red().to_string()
-        } else {
-            "clean".green().to_string()
-        };
+        // Custom views are for scripts, so skip the empty message and init offer
+        if let Some(ref format) = format {
+            for sm in &submodules {
+                println!("{}", format.render(sm));
+            }
+            return Ok(());
+        }
+        if !columns.is_empty() {
+            for line in template::columns(&submodules, &columns)? {
+                println!("{}", line);
+            }
+            return Ok(());
+        }
 
-        println!(
-            "{:<width$}  {}  {}  {}",
-            sm.name.bold(),
-            commit.dimmed(),
-            branch.cyan(),
-            status,
-            width = max_name
-        );
-    }
+        if submodules.is_empty() {
+            println!("No submodules found.");
+            return Ok(());
+        }
+
+        // Calculate column widths
+        let max_name = submodules.iter().map(|s| s.name.len()).max().unwrap_or(0);
 
-    Ok(())
+        for sm in &submodules {
+            let branch = sm.branch.as_deref().unwrap_or("(none)");
+            let commit = match (long, sm.head_commit) {
+                (true, Some(oid)) => oid.to_string(),
+                (true, None) => "-".repeat(40),
+                (false, _) => sm
+                    .head_short
+                    .clone()
+                    .unwrap_or_else(|| "-------".to_string()),
+            };
+
+            let clean = if sm.is_dirty {
+                "dirty".red().to_string()
+            } else {
+                "clean".green().to_string()
+            };
+            let status = match sm.state {
+                SubmoduleState::Initialised => clean,
+                SubmoduleState::UrlMismatch => format!("{}  {}", clean, sm.state.label().yellow()),
+                SubmoduleState::Uninitialised => sm.state.label().yellow().to_string(),
+                SubmoduleState::MissingGitdir | SubmoduleState::NotInIndex => {
+                    sm.state.label().red().to_string()
+                }
+            };
+
+            print!(
+                "{:<width$}  {}  {}  {}",
+                sm.name.bold(),
+                commit.dimmed(),
+                branch.cyan(),
+                status,
+                width = max_name
+            );
+            if long && let (Some(time), Some(author)) = (sm.head_time, &sm.head_author) {
+                print!("  {}  {}", submodule::format_time(time).dimmed(), author);
+            }
+            println!();
+        }
+
+        if offer {
+            init::offer(&submodules, jobs)?;
+        }
+        Ok(())
+    };
+
+    if watch {
+        // Offering to initialise would block the refresh loop
+        return watch::run(&repo, || list(false));
+    }
+    list(true)
 }
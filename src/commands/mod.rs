@@ -1,9 +1,27 @@
+pub mod adopt;
+pub mod attach;
+pub mod branch;
+pub mod cache;
+pub mod checkout;
 pub mod clone;
 pub mod completions;
+pub mod contains;
 pub mod diff;
+pub mod export;
 pub mod foreach;
+pub mod grep;
+pub mod history;
+pub mod import;
+pub mod init;
+pub mod log;
 pub mod ls;
 pub mod man;
+pub mod prompt;
 pub mod pull;
 pub mod rm;
 pub mod status;
+pub mod switch;
+pub mod sync;
+pub mod tui;
+pub mod unvendor;
+pub mod vendor;
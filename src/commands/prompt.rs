@@ -0,0 +1,258 @@
+use anyhow::Result;
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+use crate::cache;
+use crate::config::Config;
+use crate::submodule::{self, Detail, Filter, SubmoduleInfo, SubmoduleState};
+use crate::template::{Part, Template};
+
+/// Placeholders a prompt format can use, each counting submodules.
+const FIELDS: &[&str] = &[
+    "total",
+    "dirty",
+    "ahead",
+    "behind",
+    "detached",
+    "uninitialised",
+    "changed",
+];
+
+/// A background refresh older than this is assumed to have died.
+const REFRESH_EXPIRY: Duration = Duration::from_secs(60);
+
+pub fn run(
+    filter: Filter,
+    format: Option<String>,
+    timeout: Option<u64>,
+    stale: Option<String>,
+    refresh: bool,
+) -> Result<()> {
+    // Outside a repository a prompt segment just stays empty
+    let Ok(repo) = Repository::open_from_env() else {
+        return Ok(());
+    };
+    let config = Config::load(&repo)?;
+    let format = Format::parse(&format.unwrap_or(config.prompt_format))?;
+    let detail = format.detail();
+    let lock = repo.path().join("dom").join("prompt.lock");
+
+    if refresh {
+        let result = submodule::discover(&repo, &filter, config.jobs, detail);
+        let _ = fs::remove_file(&lock);
+        return result.map(|_| ());
+    }
+
+    // Gather on a thread so the shell never waits longer than the budget. If
+    // it runs out the thread is abandoned when the process exits.
+    let budget = Duration::from_millis(timeout.unwrap_or(config.prompt_timeout));
+    let cached = cache::enabled(&repo);
+    let filter_args = filter.args();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(submodule::discover(&repo, &filter, config.jobs, detail));
+    });
+
+    match rx.recv_timeout(budget) {
+        Ok(submodules) => {
+            let line = format.render(&Counts::of(&submodules?));
+            if !line.is_empty() {
+                println!("{}", line);
+            }
+        }
+        Err(_) => {
+            println!("{}", stale.unwrap_or(config.prompt_stale));
+            // Warm the status cache so the next prompt makes it in time
+            if cached {
+                refresh_in_background(&lock, format.source(), &filter_args);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Start `git dom prompt --refresh` detached, unless one is still running.
+/// `filter_args` narrow it to the submodules this prompt counts.
+fn refresh_in_background(lock: &Path, format: &str, filter_args: &[String]) {
+    let running = fs::metadata(lock)
+        .and_then(|m| m.modified())
+        .is_ok_and(|t| {
+            SystemTime::now()
+                .duration_since(t)
+                .is_ok_and(|age| age < REFRESH_EXPIRY)
+        });
+    if running {
+        return;
+    }
+    if let Some(dir) = lock.parent()
+        && fs::create_dir_all(dir)
+            .and_then(|_| fs::write(lock, ""))
+            .is_err()
+    {
+        return;
+    }
+
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let _ = Command::new(exe)
+        .args(["prompt", "--refresh", "--format", format])
+        .args(filter_args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+/// How many submodules are in each state.
+#[derive(Default)]
+struct Counts {
+    total: usize,
+    dirty: usize,
+    ahead: usize,
+    behind: usize,
+    detached: usize,
+    uninitialised: usize,
+    changed: usize,
+}
+
+impl Counts {
+    fn of(submodules: &[SubmoduleInfo]) -> Self {
+        let count = |f: fn(&SubmoduleInfo) -> bool| submodules.iter().filter(|sm| f(sm)).count();
+        Counts {
+            total: submodules.len(),
+            dirty: count(|sm| sm.is_dirty),
+            ahead: count(|sm| sm.ahead > 0),
+            behind: count(|sm| sm.behind > 0),
+            detached: count(|sm| sm.detached),
+            uninitialised: count(|sm| sm.state == SubmoduleState::Uninitialised),
+            changed: count(|sm| sm.parent_changed),
+        }
+    }
+
+    fn get(&self, field: &str) -> usize {
+        match field {
+            "total" => self.total,
+            "dirty" => self.dirty,
+            "ahead" => self.ahead,
+            "behind" => self.behind,
+            "detached" => self.detached,
+            "uninitialised" => self.uninitialised,
+            "changed" => self.changed,
+            _ => unreachable!("unknown field '{}'", field),
+        }
+    }
+}
+
+/// A prompt format: a [`Template`] over the [`FIELDS`] counts. Words whose
+/// placeholders are all zero are left out, so `↓%(behind)` only shows up when
+/// something is behind.
+struct Format {
+    source: String,
+    template: Template,
+}
+
+/// One space-separated word of a rendered prompt.
+#[derive(Default)]
+struct Word {
+    text: String,
+    fields: usize,
+    zeros: usize,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Self> {
+        Ok(Format {
+            source: format.to_string(),
+            template: Template::parse_fields(format, FIELDS)?,
+        })
+    }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Only the dirty check and upstream counts cost anything to gather.
+    fn detail(&self) -> Detail {
+        let mut detail = Detail::default();
+        for field in self.template.fields() {
+            match field {
+                "dirty" => detail.dirty = true,
+                "ahead" | "behind" => detail.upstream = true,
+                _ => {}
+            }
+        }
+        detail
+    }
+
+    fn render(&self, counts: &Counts) -> String {
+        let mut words = vec![Word::default()];
+        for part in self.template.parts() {
+            let word = words.last_mut().unwrap();
+            match part {
+                Part::Literal(s) => {
+                    let mut pieces = s.split(' ');
+                    word.text.push_str(pieces.next().unwrap_or(""));
+                    words.extend(pieces.map(|piece| Word {
+                        text: piece.to_string(),
+                        ..Word::default()
+                    }));
+                }
+                Part::Field(f) => {
+                    let n = counts.get(f);
+                    word.fields += 1;
+                    if n == 0 {
+                        word.zeros += 1;
+                    }
+                    word.text.push_str(&n.to_string());
+                }
+            }
+        }
+        words
+            .into_iter()
+            .filter(|w| !w.text.is_empty() && (w.fields == 0 || w.zeros < w.fields))
+            .map(|w| w.text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_words_whose_counts_are_all_zero() {
+        let format = Format::parse("⎇%(total) ✱%(dirty) ↑%(ahead) ↓%(behind)").unwrap();
+        let counts = Counts {
+            total: 3,
+            dirty: 1,
+            behind: 2,
+            ..Counts::default()
+        };
+        assert_eq!(format.render(&counts), "⎇3 ✱1 ↓2");
+        assert_eq!(format.render(&Counts::default()), "");
+    }
+
+    #[test]
+    fn handles_escapes_and_repeated_spaces() {
+        let format = Format::parse("[%(dirty)%%]  x%(total)").unwrap();
+        let counts = Counts {
+            total: 2,
+            dirty: 1,
+            ..Counts::default()
+        };
+        assert_eq!(format.render(&counts), "[1%] x2");
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(Format::parse("%(stashed)").is_err());
+        assert!(Format::parse("%(dirty").is_err());
+    }
+}
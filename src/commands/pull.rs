@@ -1,12 +1,15 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
 use git2::Repository;
+use std::io::Write;
 use std::process::Command;
 
+use crate::commands::clone;
 use crate::config::{CommitMode, Config};
-use crate::submodule;
+use crate::git;
+use crate::submodule::{self, Detail, Filter};
 
-pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<()> {
+pub fn run(filter: Filter, commit_override: Option<CommitMode>) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
     let config = Config::load(&repo)?;
     let commit_mode = commit_override.unwrap_or(config.commit_mode);
@@ -15,9 +18,22 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
         .workdir()
         .context("Bare repositories are not supported")?;
 
+    let selected = submodule::select(&repo, &filter)?;
+    if selected.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    // The update can clear the very state that selected a submodule (--behind),
+    // so remember which ones were picked to report on afterwards
+    let names: Vec<String> = selected
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").to_string())
+        .collect();
+
     println!("{}", "Fetching and updating submodules...".bold());
 
-    // Fetch all submodules (or specific one)
+    // Fetch all submodules (or the selected ones)
     let mut fetch_args = vec![
         "submodule".to_string(),
         "update".to_string(),
@@ -25,9 +41,13 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
         "--merge".to_string(),
     ];
 
-    if let Some(ref n) = name {
+    if filter.is_restricted() {
         fetch_args.push("--".to_string());
-        fetch_args.push(n.clone());
+        fetch_args.extend(
+            selected
+                .iter()
+                .map(|sm| sm.path().to_string_lossy().into_owned()),
+        );
     }
 
     // Use .status() instead of .output() to inherit stdio and show git's progress
@@ -43,7 +63,8 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
     }
 
     // Show what changed
-    let submodules = submodule::discover(&repo, name.as_deref())?;
+    let submodules =
+        submodule::discover(&repo, &Filter::names(names), config.jobs, Detail::default())?;
     let mut updated = Vec::new();
 
     for sm in &submodules {
@@ -55,7 +76,7 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         if !stdout.trim().is_empty() {
-            updated.push(&sm.name);
+            updated.push(sm);
             println!("  {} {} updated", "↑".green(), sm.name.bold());
         }
     }
@@ -65,27 +86,31 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
         return Ok(());
     }
 
+    // Only the bumped gitlinks are staged or committed, never other work in
+    // the parent
+    let paths: Vec<String> = updated
+        .iter()
+        .map(|sm| sm.path.to_string_lossy().into_owned())
+        .collect();
+    let msg = format!(
+        "Update submodule(s): {}",
+        updated
+            .iter()
+            .map(|sm| sm.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let stage = || {
+        let mut args = vec!["add", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        git::run(workdir, &args)
+    };
+
     // Handle commit
     match commit_mode {
         CommitMode::Auto => {
-            let msg = format!(
-                "Update submodule(s): {}",
-                updated
-                    .iter()
-                    .map(|s| s.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
-
-            Command::new("git")
-                .args(["add", "-A"])
-                .current_dir(workdir)
-                .output()?;
-
-            Command::new("git")
-                .args(["commit", "-m", &msg])
-                .current_dir(workdir)
-                .output()?;
+            stage()?;
+            clone::commit_paths(workdir, &msg, &paths)?;
 
             println!(
                 "{} {}",
@@ -94,10 +119,7 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
             );
         }
         CommitMode::Stage => {
-            Command::new("git")
-                .args(["add", "-A"])
-                .current_dir(workdir)
-                .output()?;
+            stage()?;
 
             println!(
                 "{} {}",
@@ -107,35 +129,18 @@ pub fn run(name: Option<String>, commit_override: Option<CommitMode>) -> Result<
         }
         CommitMode::Prompt => {
             // Show what changed
-            let diff = Command::new("git")
-                .args(["diff", "--cached", "--stat"])
-                .current_dir(workdir)
-                .output()?;
-            println!("{}", String::from_utf8_lossy(&diff.stdout));
+            let mut args = vec!["diff", "--stat", "--"];
+            args.extend(paths.iter().map(String::as_str));
+            println!("{}", git::output(workdir, &args)?);
 
             print!("Commit these changes? [Y/n] ");
+            std::io::stdout().flush()?;
             let mut input = String::new();
             std::io::stdin().read_line(&mut input)?;
 
             if input.trim().is_empty() || input.trim().to_lowercase() == "y" {
-                let msg = format!(
-                    "Update submodule(s): {}",
-                    updated
-                        .iter()
-                        .map(|s| s.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                );
-
-                Command::new("git")
-                    .args(["add", "-A"])
-                    .current_dir(workdir)
-                    .output()?;
-
-                Command::new("git")
-                    .args(["commit", "-m", &msg])
-                    .current_dir(workdir)
-                    .output()?;
+                stage()?;
+                clone::commit_paths(workdir, &msg, &paths)?;
 
                 println!("{} {}", "✓".green().bold(), "Committed.".dimmed());
             } else {
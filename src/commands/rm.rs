@@ -1,35 +1,58 @@
 use anyhow::{Context, Result, bail};
 use colored::Colorize;
-use std::io::Write;
+use std::path::Path;
 use std::process::Command;
 
 use git2::Repository;
 
-pub fn run(name: String, no_commit: bool) -> Result<()> {
+use crate::commands::clone;
+use crate::submodule::{self, Filter};
+
+pub fn run(filter: Filter, no_commit: bool) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
     let workdir = repo
         .workdir()
         .context("Bare repositories are not supported")?;
 
-    // Verify the submodule exists
-    let submodules = repo.submodules()?;
-    let sm = submodules
+    let targets: Vec<(String, String)> = submodule::select(&repo, &filter)?
         .iter()
-        .find(|s| s.name().unwrap_or("") == name || s.path().to_str().unwrap_or("") == name);
+        .map(|sm| {
+            (
+                sm.name().unwrap_or("").to_string(),
+                sm.path().to_str().unwrap_or("").to_string(),
+            )
+        })
+        .collect();
+
+    for (sm_name, sm_path) in &targets {
+        println!("{} {}", "Removing submodule".bold(), sm_name.red());
 
-    let sm = match sm {
-        Some(s) => s,
-        None => bail!("Submodule '{}' not found", name),
-    };
+        remove(workdir, sm_path)?;
 
-    let sm_path = sm.path().to_str().unwrap_or("").to_string();
-    let sm_name = sm.name().unwrap_or("").to_string();
+        println!("{} {} removed cleanly.", "✓".green().bold(), sm_name.bold());
+    }
 
-    println!("{} {}", "Removing submodule".bold(), sm_name.red());
+    let paths: Vec<String> = targets.into_iter().map(|(_, path)| path).collect();
 
+    // Prompt to commit if we're on a TTY (unless --no-commit)
+    if !no_commit && atty::is(atty::Stream::Stdin) {
+        let noun = if paths.len() == 1 {
+            "submodule"
+        } else {
+            "submodules"
+        };
+        let msg = format!("Remove {}: {}", noun, paths.join(", "));
+        clone::commit_batch(workdir, &msg, &paths, false, true)?;
+    }
+
+    Ok(())
+}
+
+/// Deinit a submodule, drop its git dir and remove it from the index and worktree.
+pub(crate) fn remove(workdir: &Path, sm_path: &str) -> Result<()> {
     // 1. Deinit the submodule
     let output = Command::new("git")
-        .args(["submodule", "deinit", "-f", "--", &sm_path])
+        .args(["submodule", "deinit", "-f", "--", sm_path])
         .current_dir(workdir)
         .output()
         .context("Failed to deinit submodule")?;
@@ -40,7 +63,7 @@ pub fn run(name: String, no_commit: bool) -> Result<()> {
     }
 
     // 2. Remove from .git/modules
-    let modules_path = workdir.join(".git").join("modules").join(&sm_path);
+    let modules_path = workdir.join(".git").join("modules").join(sm_path);
     if modules_path.exists() {
         std::fs::remove_dir_all(&modules_path)
             .with_context(|| format!("Failed to remove {}", modules_path.display()))?;
@@ -48,7 +71,7 @@ pub fn run(name: String, no_commit: bool) -> Result<()> {
 
     // 3. Remove the submodule entry and worktree
     let output = Command::new("git")
-        .args(["rm", "-f", &sm_path])
+        .args(["rm", "-f", sm_path])
         .current_dir(workdir)
         .output()
         .context("Failed to git rm submodule")?;
@@ -58,38 +81,5 @@ pub fn run(name: String, no_commit: bool) -> Result<()> {
         bail!("git rm failed: {}", stderr);
     }
 
-    println!("{} {} removed cleanly.", "✓".green().bold(), sm_name.bold());
-
-    // Prompt to commit if we're on a TTY (unless --no-commit)
-    if !no_commit && atty::is(atty::Stream::Stdin) {
-        print!("Commit to parent repo? [Y/n] ");
-        std::io::stdout().flush()?;
-
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-
-        if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("y") {
-            let msg = format!("Remove submodule: {}", sm_path);
-            let output = Command::new("git")
-                .args(["commit", "-m", &msg])
-                .current_dir(workdir)
-                .output()
-                .context("Failed to run git commit")?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                bail!("git commit failed: {}", stderr);
-            }
-
-            println!(
-                "{} {}",
-                "✓".green().bold(),
-                format!("Committed: {}", msg).dimmed()
-            );
-        } else {
-            println!("{}", "Changes left staged.".dimmed());
-        }
-    }
-
     Ok(())
 }
@@ -2,17 +2,130 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use git2::Repository;
 
-use crate::submodule;
+use crate::cache;
+use crate::commands::init;
+use crate::config::Config;
+use crate::submodule::{self, Detail, Filter, SubmoduleInfo, SubmoduleState};
+use crate::watch;
+
+/// `--exit-code` bits, combined when several apply. 1 is left for errors.
+const EXIT_DIRTY: i32 = 2;
+const EXIT_BEHIND: i32 = 4;
+const EXIT_AHEAD: i32 = 8;
+const EXIT_DETACHED: i32 = 16;
+const EXIT_PARENT_CHANGED: i32 = 32;
+
+pub fn run(
+    filter: Filter,
+    long: bool,
+    porcelain: bool,
+    exit_code: bool,
+    jobs: Option<usize>,
+    watch: bool,
+) -> Result<()> {
+    // The cache can't see worktree edits, and status (and `--exit-code` in
+    // particular) has to be exact
+    cache::disable();
 
-pub fn run(name: Option<String>) -> Result<()> {
     let repo = Repository::open_from_env().context("Not in a git repository")?;
-    let submodules = submodule::discover(&repo, name.as_deref())?;
+    let jobs = jobs.map_or_else(|| Config::load(&repo).map(|c| c.jobs), Ok)?;
+
+    if watch {
+        return watch::run(&repo, || {
+            let submodules = submodule::discover(&repo, &filter, jobs, Detail::all())?;
+            show(&submodules, long, porcelain);
+            Ok(())
+        });
+    }
 
-    if submodules.is_empty() {
+    let submodules = submodule::discover(&repo, &filter, jobs, Detail::all())?;
+    show(&submodules, long, porcelain);
+    if !porcelain && !exit_code && !submodules.is_empty() {
+        init::offer(&submodules, jobs)?;
+    }
+
+    if exit_code {
+        let code = code_for(&submodules);
+        if code != 0 {
+            std::process::exit(code);
+        }
+    }
+
+    Ok(())
+}
+
+fn show(submodules: &[SubmoduleInfo], long: bool, porcelain: bool) {
+    if porcelain {
+        for sm in submodules {
+            println!("{}", porcelain_line(sm));
+        }
+    } else if submodules.is_empty() {
         println!("No submodules found.");
-        return Ok(());
+    } else {
+        print_status(submodules, long);
     }
+}
+
+/// The `--exit-code` status for a set of submodules.
+fn code_for(submodules: &[SubmoduleInfo]) -> i32 {
+    let mut code = 0;
+    for sm in submodules {
+        if sm.is_dirty {
+            code |= EXIT_DIRTY;
+        }
+        if sm.behind > 0 {
+            code |= EXIT_BEHIND;
+        }
+        if sm.ahead > 0 {
+            code |= EXIT_AHEAD;
+        }
+        if sm.detached {
+            code |= EXIT_DETACHED;
+        }
+        if sm.parent_changed {
+            code |= EXIT_PARENT_CHANGED;
+        }
+    }
+    code
+}
+
+/// One line per submodule for scripts. The format is stable:
+///
+/// `<state> <flags> <ahead> <behind> <staged> <modified> <untracked> <head> <recorded> <index> <branch> <path>`
+///
+/// `flags` is three characters, `D` (dirty), `H` (detached HEAD) and `P`
+/// (gitlink changed in the parent), with `.` for each one that's unset.
+/// Missing commits are written as zeros and a missing branch as `-`. The path
+/// comes last so it may contain spaces.
+fn porcelain_line(sm: &SubmoduleInfo) -> String {
+    let flag = |set: bool, c: char| if set { c } else { '.' };
+    let oid = |o: Option<git2::Oid>| o.unwrap_or_else(git2::Oid::zero).to_string();
+    let branch = if sm.detached {
+        "(detached)"
+    } else {
+        sm.branch.as_deref().unwrap_or("-")
+    };
+
+    format!(
+        "{} {}{}{} {} {} {} {} {} {} {} {} {} {}",
+        sm.state.label().replace(' ', "-"),
+        flag(sm.is_dirty, 'D'),
+        flag(sm.detached, 'H'),
+        flag(sm.parent_changed, 'P'),
+        sm.ahead,
+        sm.behind,
+        sm.staged,
+        sm.modified,
+        sm.untracked,
+        oid(sm.head_commit),
+        oid(sm.recorded_commit),
+        oid(sm.staged_commit),
+        branch,
+        sm.path.display()
+    )
+}
 
+fn print_status(submodules: &[SubmoduleInfo], long: bool) {
     for (i, sm) in submodules.iter().enumerate() {
         if i > 0 {
             println!();
@@ -23,11 +136,64 @@ pub fn run(name: Option<String>) -> Result<()> {
         println!("{}", sm.name.bold());
         println!("  {} {}", "path:".dimmed(), sm.path.display());
         println!("  {} {}", "url: ".dimmed(), sm.url);
+        if !sm.groups.is_empty() {
+            println!("  {} {}", "grp: ".dimmed(), sm.groups.join(", "));
+        }
+
+        match sm.state {
+            SubmoduleState::Initialised => {}
+            SubmoduleState::Uninitialised => println!(
+                "  {} {}",
+                "○".yellow(),
+                "not initialised (run `git dom init` to clone it)".yellow()
+            ),
+            SubmoduleState::MissingGitdir => println!(
+                "  {} {}",
+                "✗".red(),
+                "git directory is missing (remove the directory and run `git dom init`)".red()
+            ),
+            SubmoduleState::UrlMismatch => println!(
+                "  {} {}",
+                "!".yellow(),
+                "origin differs from .gitmodules (run `git dom sync`)".yellow()
+            ),
+            SubmoduleState::NotInIndex => println!(
+                "  {} {}",
+                "✗".red(),
+                "listed in .gitmodules but has no gitlink in the index".red()
+            ),
+        }
+
+        // Nothing more to report without a checkout
+        if sm.head_commit.is_none() {
+            continue;
+        }
+
         println!("  {} {}", "on:  ".dimmed(), branch_display.cyan());
 
-        if let Some(ref commit) = sm.head_commit {
+        if let Some(commit) = sm.head_commit {
+            let id = if long {
+                commit.to_string()
+            } else {
+                sm.head_short.clone().unwrap_or_default()
+            };
             let msg = sm.head_message.as_deref().unwrap_or("");
-            println!("  {} {} {}", "head:".dimmed(), commit.yellow(), msg);
+            println!("  {} {} {}", "head:".dimmed(), id.yellow(), msg);
+        }
+
+        if long {
+            if let Some(ref author) = sm.head_author {
+                println!("  {} {}", "auth:".dimmed(), author);
+            }
+            if let Some(time) = sm.head_time {
+                println!("  {} {}", "date:".dimmed(), submodule::format_time(time));
+            }
+            // The gitlinks the parent has for this submodule, in HEAD and the index
+            let gitlink = |oid: Option<git2::Oid>| {
+                oid.map_or_else(|| "(none)".to_string(), |o| o.to_string())
+            };
+            println!("  {} {}", "rec: ".dimmed(), gitlink(sm.recorded_commit));
+            println!("  {} {}", "idx: ".dimmed(), gitlink(sm.staged_commit));
         }
 
         // Ahead/behind
@@ -75,6 +241,40 @@ pub fn run(name: Option<String>) -> Result<()> {
             );
         }
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn info() -> SubmoduleInfo {
+        SubmoduleInfo::new(
+            "lib".to_string(),
+            PathBuf::from("libs/my lib"),
+            "https://example.com/lib.git".to_string(),
+        )
+    }
+
+    #[test]
+    fn exit_code_combines_a_bit_per_condition() {
+        let mut dirty = info();
+        dirty.is_dirty = true;
+        let mut behind = info();
+        behind.behind = 2;
+        assert_eq!(code_for(&[info()]), 0);
+        assert_eq!(code_for(&[dirty, behind]), EXIT_DIRTY | EXIT_BEHIND);
+    }
+
+    #[test]
+    fn porcelain_puts_the_path_last() {
+        let zero = "0".repeat(40);
+        assert_eq!(
+            porcelain_line(&info()),
+            format!(
+                "uninitialised ... 0 0 0 0 0 {} {} {} - libs/my lib",
+                zero, zero, zero
+            )
+        );
+    }
 }
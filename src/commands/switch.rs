@@ -0,0 +1,188 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::{BranchType, Repository, StatusOptions};
+use std::path::Path;
+
+use crate::git;
+use crate::submodule::{self, Filter};
+
+pub fn run(branch: String, filter: Filter) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let submodules = submodule::select(&repo, &filter)?;
+    // Uninitialised submodules have nothing to switch
+    let initialised: Vec<_> = submodules
+        .iter()
+        .filter_map(|sm| Some((sm, sm.open().ok()?)))
+        .collect();
+
+    // Refuse up front so we never leave the tree half switched, or detach
+    // every submodule over a mistyped branch name
+    if !has_branch(&repo, &branch) && !initialised.iter().any(|(_, r)| has_branch(r, &branch)) {
+        bail!("no branch '{}' in the parent or any submodule", branch);
+    }
+
+    let mut dirty = Vec::new();
+    if parent_is_dirty(&repo)? {
+        dirty.push("(parent)".to_string());
+    }
+    for (sm, sub_repo) in &initialised {
+        if is_dirty(sub_repo, false)? {
+            dirty.push(sm.name().unwrap_or("").to_string());
+        }
+    }
+    if !dirty.is_empty() {
+        bail!(
+            "Uncommitted changes in {}; commit or stash them first",
+            dirty.join(", ")
+        );
+    }
+    let checked: Vec<String> = initialised
+        .iter()
+        .map(|(sm, _)| sm.name().unwrap_or("").to_string())
+        .collect();
+
+    // Switch the parent first so the fallback commits come from its new HEAD
+    let parent_switched = has_branch(&repo, &branch);
+    if parent_switched {
+        git::run(workdir, &["checkout", "--quiet", &branch])?;
+    }
+
+    // The switch may have added or removed submodules, so read them afresh.
+    // A selector that only matched removed ones leaves nothing to do.
+    let repo = Repository::open(workdir)?;
+    let submodules = submodule::select(&repo, &filter).unwrap_or_default();
+
+    let max_name = submodules
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").len())
+        .max()
+        .unwrap_or(0)
+        .max("(parent)".len());
+
+    if parent_switched {
+        print_switched("(parent)", &branch, max_name);
+    } else {
+        print_missing("(parent)", "no such branch, staying put", max_name);
+    }
+
+    let mut failed = 0;
+    for sm in &submodules {
+        let name = sm.name().unwrap_or("");
+        let Ok(sub_repo) = sm.open() else {
+            print_missing(name, "not initialised", max_name);
+            continue;
+        };
+        // Submodules the switch brought in weren't checked above
+        let result = if !checked.iter().any(|c| c == name) && is_dirty(&sub_repo, false)? {
+            Err(anyhow::anyhow!("uncommitted changes, left alone"))
+        } else {
+            switch(
+                &repo,
+                &workdir.join(sm.path()),
+                sm.path(),
+                &sub_repo,
+                &branch,
+            )
+        };
+        match result {
+            Ok(Switched::Branch) => print_switched(name, &branch, max_name),
+            Ok(Switched::Recorded(oid)) => {
+                print_missing(name, &format!("recorded commit {:.7}", oid), max_name)
+            }
+            Ok(Switched::Neither) => print_missing(name, "no branch or recorded commit", max_name),
+            Err(e) => {
+                failed += 1;
+                eprintln!("  {} {:<max_name$}  {}", "✗".red().bold(), name.bold(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} submodule(s) could not be switched", failed);
+    }
+
+    Ok(())
+}
+
+enum Switched {
+    Branch,
+    Recorded(git2::Oid),
+    Neither,
+}
+
+/// Check out `branch` in one submodule, or the commit the parent records.
+fn switch(
+    repo: &Repository,
+    sub_dir: &Path,
+    sm_path: &Path,
+    sub_repo: &Repository,
+    branch: &str,
+) -> Result<Switched> {
+    if has_branch(sub_repo, branch) {
+        // `git checkout` creates a tracking branch from origin when needed
+        git::run(sub_dir, &["checkout", "--quiet", branch])?;
+        return Ok(Switched::Branch);
+    }
+
+    let Some(recorded) = submodule::recorded_commit(repo, sm_path) else {
+        return Ok(Switched::Neither);
+    };
+
+    let head = sub_repo.head().ok().and_then(|h| h.target());
+    if head != Some(recorded) {
+        let sha = recorded.to_string();
+        if sub_repo.find_commit(recorded).is_err() {
+            git::run(sub_dir, &["fetch", "--quiet", "origin"])?;
+        }
+        git::run(sub_dir, &["checkout", "--quiet", "--detach", &sha])?;
+    }
+    Ok(Switched::Recorded(recorded))
+}
+
+/// A local branch, or a remote one `git checkout` will pick up.
+fn has_branch(repo: &Repository, branch: &str) -> bool {
+    repo.find_branch(branch, BranchType::Local).is_ok()
+        || repo
+            .find_branch(&format!("origin/{}", branch), BranchType::Remote)
+            .is_ok()
+}
+
+/// Changes in the parent's own files, or a staged gitlink. Submodule
+/// worktrees are checked on their own.
+fn parent_is_dirty(repo: &Repository) -> Result<bool> {
+    if is_dirty(repo, true)? {
+        return Ok(true);
+    }
+    let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let staged = repo.diff_tree_to_index(head.as_ref(), None, None)?;
+    Ok(staged.deltas().len() > 0)
+}
+
+fn is_dirty(repo: &Repository, exclude_submodules: bool) -> Result<bool> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false)
+        .exclude_submodules(exclude_submodules);
+    Ok(!repo.statuses(Some(&mut opts))?.is_empty())
+}
+
+fn print_switched(name: &str, branch: &str, width: usize) {
+    println!(
+        "  {} {:<width$}  {}",
+        "✓".green().bold(),
+        name.bold(),
+        branch.cyan()
+    );
+}
+
+fn print_missing(name: &str, note: &str, width: usize) {
+    println!(
+        "  {} {:<width$}  {}",
+        "·".dimmed(),
+        name.bold(),
+        note.dimmed()
+    );
+}
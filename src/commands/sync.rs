@@ -0,0 +1,235 @@
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use colored::Colorize;
+use git2::Repository;
+
+use crate::git;
+use crate::submodule::{self, Filter};
+
+/// Where `sync` takes the correct value from.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Source {
+    /// The committed .gitmodules file
+    Gitmodules,
+    /// The parent's .git/config
+    Config,
+    /// Each submodule's `origin` remote (URLs only)
+    Remote,
+}
+
+/// One setting of one submodule as seen by each source.
+struct Setting {
+    submodule: String,
+    key: &'static str,
+    gitmodules: Option<String>,
+    config: Option<String>,
+    /// Only URLs have a remote counterpart
+    remote: Option<String>,
+}
+
+impl Setting {
+    /// Whether the sources disagree. Submodules that were never initialised
+    /// have no local settings, so they can't drift.
+    fn drifted(&self) -> bool {
+        if self.key != "url" {
+            // A local override of a value .gitmodules leaves unset still differs
+            return self.config.is_some() && self.config != self.gitmodules;
+        }
+        let values: Vec<&String> = [&self.gitmodules, &self.config, &self.remote]
+            .into_iter()
+            .flatten()
+            .collect();
+        self.config.is_some() && values.windows(2).any(|w| w[0] != w[1])
+    }
+
+    fn value(&self, source: Source) -> Option<&String> {
+        match source {
+            Source::Gitmodules => self.gitmodules.as_ref(),
+            Source::Config => self.config.as_ref(),
+            Source::Remote => self.remote.as_ref(),
+        }
+    }
+}
+
+pub fn run(filter: Filter, from: Source, check: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let submodules = submodule::select(&repo, &filter)?;
+    if submodules.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    let gitmodules_path = workdir.join(".gitmodules");
+    let mut gitmodules = git2::Config::open(&gitmodules_path)
+        .with_context(|| format!("Failed to read {}", gitmodules_path.display()))?;
+    let mut local =
+        git2::Config::open(&repo.path().join("config")).context("Failed to read .git/config")?;
+
+    let mut settings = Vec::new();
+
+    for sm in &submodules {
+        let sm_name = sm.name().unwrap_or("").to_string();
+        let key = |k: &str| format!("submodule.{}.{}", sm_name, k);
+
+        let config_url = local.get_string(&key("url")).ok();
+
+        // Read the raw config value; `Remote::url` applies insteadOf rewrites
+        let remote_url = sm
+            .open()
+            .ok()
+            .and_then(|r| r.config().ok()?.get_string("remote.origin.url").ok());
+
+        settings.push(Setting {
+            submodule: sm_name.clone(),
+            key: "url",
+            gitmodules: gitmodules
+                .get_string(&key("url"))
+                .ok()
+                .map(|u| submodule::resolve_url(&repo, &u)),
+            config: config_url,
+            remote: remote_url,
+        });
+
+        for k in ["branch", "update"] {
+            settings.push(Setting {
+                submodule: sm_name.clone(),
+                key: k,
+                gitmodules: gitmodules.get_string(&key(k)).ok(),
+                config: local.get_string(&key(k)).ok(),
+                remote: None,
+            });
+        }
+    }
+
+    let drifted: Vec<&Setting> = settings.iter().filter(|s| s.drifted()).collect();
+
+    if drifted.is_empty() {
+        println!("{}", "All submodules in sync.".green());
+        return Ok(());
+    }
+
+    print_table(&drifted, from);
+
+    if check {
+        bail!("{} setting(s) out of sync", drifted.len());
+    }
+
+    let mut fixed = 0;
+    let mut gitmodules_changed = false;
+
+    for s in &drifted {
+        // Remotes only carry URLs
+        if from == Source::Remote && s.key != "url" {
+            continue;
+        }
+
+        let value = s.value(from);
+        let key = format!("submodule.{}.{}", s.submodule, s.key);
+
+        // branch and update may legitimately be unset; a URL may not
+        if value.is_none() && s.key == "url" {
+            eprintln!(
+                "  {} {} url: no value in {}, skipping",
+                "!".yellow(),
+                s.submodule,
+                source_label(from)
+            );
+            continue;
+        }
+
+        if from != Source::Gitmodules && s.gitmodules.as_ref() != value {
+            set_or_remove(&mut gitmodules, &key, value)?;
+            gitmodules_changed = true;
+            fixed += 1;
+        }
+        if from != Source::Config && s.config.as_ref() != value {
+            set_or_remove(&mut local, &key, value)?;
+            fixed += 1;
+        }
+        if let (Some(url), Some(_)) = (value, &s.remote)
+            && from != Source::Remote
+            && s.remote.as_ref() != value
+        {
+            let sub_repo = repo.find_submodule(&s.submodule)?.open()?;
+            sub_repo.remote_set_url("origin", url)?;
+            fixed += 1;
+        }
+    }
+
+    if gitmodules_changed {
+        git::run(workdir, &["add", ".gitmodules"])?;
+    }
+
+    println!(
+        "{} Fixed {} setting(s) from {}.",
+        "✓".green().bold(),
+        fixed,
+        source_label(from)
+    );
+
+    Ok(())
+}
+
+fn set_or_remove(config: &mut git2::Config, key: &str, value: Option<&String>) -> Result<()> {
+    match value {
+        Some(v) => config.set_str(key, v)?,
+        None => config.remove(key)?,
+    }
+    Ok(())
+}
+
+fn source_label(source: Source) -> &'static str {
+    match source {
+        Source::Gitmodules => ".gitmodules",
+        Source::Config => ".git/config",
+        Source::Remote => "origin",
+    }
+}
+
+fn print_table(drifted: &[&Setting], from: Source) {
+    let width = |f: &dyn Fn(&Setting) -> usize, header: &str| {
+        drifted
+            .iter()
+            .map(|s| f(s))
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    };
+    let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+
+    let name_w = width(&|s| s.submodule.len(), "submodule");
+    let key_w = width(&|s| s.key.len(), "setting");
+    let gm_w = width(&|s| show(&s.gitmodules).len(), ".gitmodules");
+    let cfg_w = width(&|s| show(&s.config).len(), ".git/config");
+
+    let header = format!(
+        "{:<name_w$}  {:<key_w$}  {:<gm_w$}  {:<cfg_w$}  {}",
+        "submodule", "setting", ".gitmodules", ".git/config", "origin"
+    );
+    println!("{}", header.dimmed());
+
+    for s in drifted {
+        // Highlight the values that will be overwritten
+        let cell = |source: Source, v: &Option<String>, w: usize| {
+            let text = format!("{:<w$}", show(v));
+            if source == from || v.is_none() || s.value(from) == v.as_ref() {
+                text.normal()
+            } else {
+                text.red()
+            }
+        };
+
+        println!(
+            "{:<name_w$}  {:<key_w$}  {}  {}  {}",
+            s.submodule.bold(),
+            s.key,
+            cell(Source::Gitmodules, &s.gitmodules, gm_w),
+            cell(Source::Config, &s.config, cfg_w),
+            cell(Source::Remote, &s.remote, 0),
+        );
+    }
+}
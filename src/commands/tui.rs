@@ -0,0 +1,427 @@
+use anyhow::{Context, Result, bail};
+use git2::Repository;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::commands::{attach, checkout, pull};
+use crate::config::{CommitMode, Config};
+use crate::git;
+use crate::submodule::{self, Detail, Filter, SubmoduleInfo, SubmoduleState};
+
+/// How long the table sits unchanged before it's gathered again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+const HELP: &str =
+    "↑↓ move  ⏎ details  p pull  c checkout recorded  a attach  s shell  r refresh  q quit";
+
+pub fn run(filter: Filter, jobs: Option<usize>) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?
+        .to_path_buf();
+    let jobs = jobs.map_or_else(|| Config::load(&repo).map(|c| c.jobs), Ok)?;
+
+    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout) {
+        bail!("git dom tui needs an interactive terminal");
+    }
+
+    // Gather once up front so a bad selector fails before the screen changes
+    let submodules = submodule::discover(&repo, &filter, jobs, Detail::all())?;
+    if submodules.is_empty() {
+        println!("No submodules found.");
+        return Ok(());
+    }
+
+    let mut app = App {
+        workdir,
+        filter,
+        jobs,
+        submodules,
+        table: TableState::default().with_selected(0),
+        details: None,
+        pending: None,
+        refreshed: Instant::now(),
+        message: None,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Files and log of the submodule being drilled into.
+struct Details {
+    name: String,
+    files: Vec<String>,
+    log: Vec<String>,
+    scroll: u16,
+}
+
+struct App {
+    workdir: PathBuf,
+    filter: Filter,
+    jobs: usize,
+    submodules: Vec<SubmoduleInfo>,
+    table: TableState,
+    details: Option<Details>,
+    /// A refresh running in the background.
+    pending: Option<Receiver<Result<Vec<SubmoduleInfo>>>>,
+    refreshed: Instant,
+    /// Shown in the footer until the next key press.
+    message: Option<String>,
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            self.receive();
+            if self.pending.is_none() && self.refreshed.elapsed() >= REFRESH_INTERVAL {
+                self.refresh();
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(Duration::from_millis(250))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            self.message = None;
+
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Esc if self.details.is_some() => self.details = None,
+                KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+                KeyCode::Enter => self.open_details()?,
+                KeyCode::Char('r') => self.refresh(),
+                KeyCode::Char('p') => self.action(terminal, Action::Pull)?,
+                KeyCode::Char('c') => self.action(terminal, Action::Checkout)?,
+                KeyCode::Char('a') => self.action(terminal, Action::Attach)?,
+                KeyCode::Char('s') => self.action(terminal, Action::Shell)?,
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<&SubmoduleInfo> {
+        self.table.selected().and_then(|i| self.submodules.get(i))
+    }
+
+    fn scroll(&mut self, by: i32) {
+        if let Some(details) = &mut self.details {
+            details.scroll = details.scroll.saturating_add_signed(by as i16);
+        } else if by < 0 {
+            self.table.select_previous();
+        } else {
+            self.table.select_next();
+        }
+    }
+
+    /// Start gathering in the background; the table keeps showing the last
+    /// results until it's done.
+    fn refresh(&mut self) {
+        if self.pending.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let workdir = self.workdir.clone();
+        let filter = self.filter.clone();
+        let jobs = self.jobs;
+        std::thread::spawn(move || {
+            let result = Repository::open(&workdir)
+                .map_err(Into::into)
+                .and_then(|repo| submodule::discover(&repo, &filter, jobs, Detail::all()));
+            let _ = tx.send(result);
+        });
+        self.pending = Some(rx);
+    }
+
+    fn receive(&mut self) {
+        let Some(rx) = &self.pending else {
+            return;
+        };
+        let Ok(result) = rx.try_recv() else {
+            return;
+        };
+        self.pending = None;
+        self.refreshed = Instant::now();
+
+        match result {
+            Ok(submodules) => {
+                // Keep the same submodule selected if it's still there
+                let name = self.selected().map(|sm| sm.name.clone());
+                self.submodules = submodules;
+                let index = name
+                    .and_then(|n| self.submodules.iter().position(|sm| sm.name == n))
+                    .unwrap_or(0);
+                self.table.select(Some(index));
+            }
+            Err(e) => self.message = Some(format!("Refresh failed: {:#}", e)),
+        }
+    }
+
+    fn open_details(&mut self) -> Result<()> {
+        let Some(sm) = self.selected() else {
+            return Ok(());
+        };
+        let dir = self.workdir.join(&sm.path);
+        let lines = |args: &[&str]| -> Vec<String> {
+            git::output(&dir, args)
+                .map(|out| out.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+
+        let (files, log) = if sm.head_commit.is_some() {
+            (
+                lines(&["status", "--short", "--branch"]),
+                lines(&["log", "--oneline", "--decorate", "-n", "100"]),
+            )
+        } else {
+            (vec![sm.state.label().to_string()], Vec::new())
+        };
+
+        self.details = Some(Details {
+            name: sm.name.clone(),
+            files,
+            log,
+            scroll: 0,
+        });
+        Ok(())
+    }
+
+    /// Run an action on the selected submodule with the terminal handed back
+    /// to it, then refresh.
+    fn action(&mut self, terminal: &mut DefaultTerminal, action: Action) -> Result<()> {
+        let Some(sm) = self.selected() else {
+            return Ok(());
+        };
+        let name = sm.name.clone();
+        let dir = self.workdir.join(&sm.path);
+
+        suspend(terminal, || {
+            let single = Filter::names(vec![name.clone()]);
+            let result = match action {
+                // Stage the bump for the user to commit, whatever dom.commit says
+                Action::Pull => pull::run(single, Some(CommitMode::Stage)),
+                Action::Checkout => checkout::run(single, false, false, false, false),
+                Action::Attach => attach::run(single),
+                Action::Shell => shell(&dir),
+            };
+            match result {
+                // The shell's own exit is the cue to come back
+                Ok(()) if matches!(action, Action::Shell) => return Ok(()),
+                Ok(()) => {}
+                Err(e) => eprintln!("Error: {:#}", e),
+            }
+            print!("\nPress Enter to return to git dom tui. ");
+            std::io::stdout().flush()?;
+            std::io::stdin().read_line(&mut String::new())?;
+            Ok(())
+        })?;
+
+        if self.details.is_some() {
+            self.open_details()?;
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        if let Some(details) = &self.details {
+            let [files, log] = Layout::vertical([
+                Constraint::Length(details.files.len().clamp(1, 12) as u16 + 2),
+                Constraint::Min(1),
+            ])
+            .areas(main);
+
+            frame.render_widget(
+                Paragraph::new(text(&details.files))
+                    .block(Block::bordered().title(format!(" {} ", details.name))),
+                files,
+            );
+            frame.render_widget(
+                Paragraph::new(text(&details.log))
+                    .scroll((details.scroll, 0))
+                    .block(Block::bordered().title(" log ")),
+                log,
+            );
+        } else {
+            let title = if self.pending.is_some() {
+                " submodules (refreshing…) ".to_string()
+            } else {
+                format!(" submodules ({}) ", self.submodules.len())
+            };
+            frame.render_stateful_widget(
+                table(&self.submodules).block(Block::bordered().title(title)),
+                main,
+                &mut self.table,
+            );
+        }
+
+        let footer_text = match &self.message {
+            Some(message) => Line::styled(message.as_str(), Style::new().fg(Color::Red)),
+            None if self.details.is_some() => Line::styled(
+                HELP.replace("⏎ details", "esc back"),
+                Style::new().add_modifier(Modifier::DIM),
+            ),
+            None => Line::styled(HELP, Style::new().add_modifier(Modifier::DIM)),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Pull,
+    Checkout,
+    Attach,
+    Shell,
+}
+
+fn table(submodules: &[SubmoduleInfo]) -> Table<'_> {
+    let number = |n: usize, colour: Color| {
+        if n == 0 {
+            Cell::from("")
+        } else {
+            Cell::from(Span::styled(n.to_string(), Style::new().fg(colour)))
+        }
+    };
+
+    let rows = submodules.iter().map(|sm| {
+        let branch = match sm.state {
+            SubmoduleState::Initialised | SubmoduleState::UrlMismatch if sm.detached => {
+                Span::styled("(detached)", Style::new().fg(Color::Yellow))
+            }
+            SubmoduleState::Initialised | SubmoduleState::UrlMismatch => Span::styled(
+                sm.branch.clone().unwrap_or_default(),
+                Style::new().fg(Color::Cyan),
+            ),
+            _ => Span::styled(sm.state.label(), Style::new().fg(Color::Red)),
+        };
+        let name = if sm.is_dirty {
+            Span::styled(sm.name.as_str(), Style::new().fg(Color::Yellow))
+        } else {
+            Span::raw(sm.name.as_str())
+        };
+
+        Row::new([
+            Cell::from(name),
+            Cell::from(branch),
+            number(sm.ahead, Color::Green),
+            number(sm.behind, Color::Red),
+            number(sm.staged, Color::Green),
+            number(sm.modified, Color::Yellow),
+            number(sm.untracked, Color::DarkGray),
+            Cell::from(pointer(sm)),
+        ])
+    });
+
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Length(4),
+        Constraint::Length(4),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(11),
+    ];
+
+    Table::new(rows, widths)
+        .header(
+            Row::new([
+                "NAME", "BRANCH", "↑", "↓", "STAGED", "MOD", "UNTR", "PARENT",
+            ])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+}
+
+fn text(lines: &[String]) -> Vec<Line<'_>> {
+    lines.iter().map(|l| Line::raw(l.as_str())).collect()
+}
+
+/// How the submodule's HEAD relates to the commit the parent records for it.
+fn pointer(sm: &SubmoduleInfo) -> &'static str {
+    if sm.head_commit.is_none() {
+        ""
+    } else if sm.staged_commit != sm.recorded_commit {
+        "staged"
+    } else if sm.head_commit != sm.recorded_commit {
+        "moved"
+    } else {
+        "at recorded"
+    }
+}
+
+/// Leave the alternate screen while `f` runs, then take it back.
+fn suspend(terminal: &mut DefaultTerminal, f: impl FnOnce() -> Result<()>) -> Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+
+    let result = f();
+
+    execute!(std::io::stdout(), EnterAlternateScreen)?;
+    terminal::enable_raw_mode()?;
+    terminal.clear()?;
+    result
+}
+
+fn shell(dir: &Path) -> Result<()> {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    println!(
+        "Starting {} in {}; exit to return to git dom tui.",
+        shell,
+        dir.display()
+    );
+    Command::new(&shell)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("Failed to start {}", shell))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_parent_pointer() {
+        let oid = |n: u8| Some(git2::Oid::from_bytes(&[n; 20]).unwrap());
+        let mut sm = SubmoduleInfo::new("a".into(), PathBuf::from("a"), String::new());
+        assert_eq!(pointer(&sm), "");
+
+        sm.head_commit = oid(1);
+        sm.recorded_commit = oid(1);
+        sm.staged_commit = oid(1);
+        assert_eq!(pointer(&sm), "at recorded");
+
+        sm.head_commit = oid(2);
+        assert_eq!(pointer(&sm), "moved");
+
+        sm.staged_commit = oid(2);
+        assert_eq!(pointer(&sm), "staged");
+    }
+}
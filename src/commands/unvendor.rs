@@ -0,0 +1,132 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::Repository;
+use std::fs;
+
+use crate::commands::clone::{self, PlannedClone};
+use crate::commands::vendor::{self, VENDOR_FILE};
+use crate::git;
+use crate::submodule;
+
+pub fn run(path: String, no_commit: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let path = path.trim_end_matches('/');
+    let vendored = vendor::read_vendored(workdir)?;
+    let Some(info) = vendored.iter().find(|v| v.path == path || v.name == path) else {
+        bail!("'{}' is not recorded in {}", path, VENDOR_FILE);
+    };
+
+    let pending = git::output(
+        workdir,
+        &[
+            "status",
+            "--porcelain",
+            "--untracked-files=no",
+            "--",
+            &info.path,
+        ],
+    )?;
+    if !pending.trim().is_empty() {
+        bail!(
+            "{} has uncommitted changes; commit or discard them first",
+            info.path
+        );
+    }
+
+    println!(
+        "{} {} at {}",
+        "Restoring submodule".bold(),
+        info.url.cyan(),
+        format!("{:.7}", info.commit).yellow()
+    );
+
+    let planned = PlannedClone {
+        git_url: info.url.clone(),
+        sub_path: info.path.clone(),
+        branch: info.branch.clone(),
+        revision: Some(info.commit.clone()),
+        groups: info.groups.clone(),
+    };
+
+    // Clone next to the parent's git dir first so a failed fetch leaves the
+    // vendored files untouched.
+    let staging = repo.path().join("dom").join("unvendor");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    // Relative URLs are recorded as-is but cloned from the parent's origin
+    let staged_clone = PlannedClone {
+        git_url: submodule::resolve_url(&repo, &info.url),
+        sub_path: staging.to_string_lossy().into_owned(),
+        ..planned
+    };
+    if let Err(e) = clone::clone_worktree(workdir, &staged_clone) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let planned = PlannedClone {
+        git_url: info.url.clone(),
+        sub_path: info.path.clone(),
+        ..staged_clone
+    };
+
+    git::run(workdir, &["rm", "-r", "-q", "--", &info.path])?;
+
+    // Ignored files (build output and the like) survive `git rm`
+    let dest = workdir.join(&info.path);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)
+            .with_context(|| format!("Failed to remove {}", dest.display()))?;
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&staging, &dest)
+        .with_context(|| format!("Failed to move clone to {}", dest.display()))?;
+
+    clone::register(workdir, &planned)?;
+
+    // `git submodule add` names the submodule after its path
+    let settings = [("shallow", &info.shallow), ("update", &info.update)];
+    for (key, value) in settings {
+        if let Some(value) = value {
+            let key = format!("submodule.{}.{}", info.path, key);
+            git::run(workdir, &["config", "--file", ".gitmodules", &key, value])?;
+        }
+    }
+    git::run(workdir, &["add", ".gitmodules"])?;
+
+    git::run(
+        workdir,
+        &[
+            "config",
+            "--file",
+            VENDOR_FILE,
+            "--remove-section",
+            &format!("vendor.{}", info.name),
+        ],
+    )?;
+    if vendored.len() == 1 {
+        fs::remove_file(workdir.join(VENDOR_FILE))?;
+    }
+    git::run(workdir, &["add", "-A", "--", VENDOR_FILE])?;
+
+    println!(
+        "{} {} restored as a submodule.",
+        "✓".green().bold(),
+        info.path.bold()
+    );
+
+    // Prompt to commit if we're on a TTY (unless --no-commit)
+    if !no_commit && atty::is(atty::Stream::Stdin) {
+        let msg = format!("Unvendor submodule: {}", info.path);
+        let paths = [info.path.clone(), VENDOR_FILE.to_string()];
+        clone::commit_batch(workdir, &msg, &paths, false, true)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,302 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use git2::{ObjectType, Repository, StatusOptions};
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{clone, rm};
+use crate::git;
+use crate::submodule::{self, Filter};
+
+/// File in the parent repo recording where vendored trees came from.
+pub(crate) const VENDOR_FILE: &str = ".gitvendored";
+
+/// A vendored tree recorded in `.gitvendored`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct VendoredInfo {
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) url: String,
+    pub(crate) commit: String,
+    pub(crate) branch: Option<String>,
+    /// `.gitmodules` settings restored when the tree becomes a submodule again.
+    pub(crate) groups: Vec<String>,
+    pub(crate) shallow: Option<String>,
+    pub(crate) update: Option<String>,
+}
+
+pub fn run(filter: Filter, no_commit: bool) -> Result<()> {
+    let repo = Repository::open_from_env().context("Not in a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+
+    let names: Vec<String> = submodule::select(&repo, &filter)?
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").to_string())
+        .collect();
+
+    let mut paths = Vec::new();
+    for name in &names {
+        paths.push(vendor(&repo, workdir, name)?);
+    }
+
+    // Prompt to commit if we're on a TTY (unless --no-commit)
+    if !no_commit && atty::is(atty::Stream::Stdin) {
+        let noun = if paths.len() == 1 {
+            "submodule"
+        } else {
+            "submodules"
+        };
+        let msg = format!("Vendor {}: {}", noun, paths.join(", "));
+        paths.push(VENDOR_FILE.to_string());
+        clone::commit_batch(workdir, &msg, &paths, false, true)?;
+    }
+
+    Ok(())
+}
+
+/// Replace the submodule `name` with its files and record it in `.gitvendored`,
+/// returning its path.
+fn vendor(repo: &Repository, workdir: &Path, name: &str) -> Result<String> {
+    // Looked up afresh since vendoring an earlier submodule rewrites .gitmodules
+    let sm = repo.find_submodule(name)?;
+
+    let sm_path = sm.path().to_str().unwrap_or("").to_string();
+    let sm_name = sm.name().unwrap_or("").to_string();
+    let url = sm.url().unwrap_or("").to_string();
+    let branch = sm.branch().map(|b| b.to_string());
+    let groups = submodule::groups(repo, &sm_name);
+    let gitmodules = git2::Config::open(&workdir.join(".gitmodules"))?;
+    let setting = |key: &str| {
+        gitmodules
+            .get_string(&format!("submodule.{}.{}", sm_name, key))
+            .ok()
+    };
+    let shallow = setting("shallow");
+    let update = setting("update");
+
+    let sub_repo = sm
+        .open()
+        .with_context(|| format!("Submodule '{}' is not initialised", sm_name))?;
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    if !sub_repo.statuses(Some(&mut opts))?.is_empty() {
+        bail!(
+            "Submodule '{}' has uncommitted changes; commit or stash them first",
+            sm_name
+        );
+    }
+
+    let commit = sub_repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .with_context(|| format!("Submodule '{}' has no HEAD commit", sm_name))?;
+
+    println!(
+        "{} {} at {}",
+        "Vendoring submodule".bold(),
+        sm_name.cyan(),
+        format!("{:.7}", commit.id()).yellow()
+    );
+
+    // Export the tree next to the parent's git dir first so the submodule can
+    // be removed before the files take its place.
+    let staging = repo.path().join("dom").join("vendor");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    export_tree(&sub_repo, &commit.tree()?, &staging)?;
+
+    rm::remove(workdir, &sm_path)?;
+
+    let dest = workdir.join(&sm_path);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)?;
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&staging, &dest)
+        .with_context(|| format!("Failed to move vendored files to {}", dest.display()))?;
+
+    record(
+        workdir,
+        &VendoredInfo {
+            name: sm_name.clone(),
+            path: sm_path.clone(),
+            url,
+            commit: commit.id().to_string(),
+            branch,
+            groups,
+            shallow,
+            update,
+        },
+    )?;
+
+    // Force-add: every file came from the submodule's tree, even if a
+    // .gitignore inside it would otherwise skip some.
+    git::run(workdir, &["add", "-f", "--", &sm_path, VENDOR_FILE])?;
+
+    println!(
+        "{} {} vendored into {}.",
+        "✓".green().bold(),
+        sm_name.bold(),
+        sm_path
+    );
+
+    Ok(sm_path)
+}
+
+/// Write `info` to `.gitvendored`.
+fn record(workdir: &Path, info: &VendoredInfo) -> Result<()> {
+    let section = format!("vendor.{}", info.name);
+    let mut keys = vec![
+        ("path", info.path.as_str()),
+        ("url", &info.url),
+        ("commit", &info.commit),
+    ];
+    keys.extend(info.branch.as_deref().map(|b| ("branch", b)));
+    keys.extend(info.groups.iter().map(|g| ("group", g.as_str())));
+    keys.extend(info.shallow.as_deref().map(|s| ("shallow", s)));
+    keys.extend(info.update.as_deref().map(|u| ("update", u)));
+    for (key, value) in keys {
+        // Groups are the one key that may repeat
+        let mut args = vec!["config", "--file", VENDOR_FILE];
+        if key == "group" {
+            args.push("--add");
+        }
+        let key = format!("{}.{}", section, key);
+        args.extend([key.as_str(), value]);
+        git::run(workdir, &args)?;
+    }
+    Ok(())
+}
+
+/// Read every entry recorded in `.gitvendored`.
+pub(crate) fn read_vendored(workdir: &Path) -> Result<Vec<VendoredInfo>> {
+    let file = workdir.join(VENDOR_FILE);
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let config =
+        git2::Config::open(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+
+    let mut names = Vec::new();
+    let mut entries = config.entries(Some(r"^vendor\..*\.path$"))?;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        if let Some(name) = entry
+            .name()
+            .and_then(|n| n.strip_prefix("vendor."))
+            .and_then(|n| n.strip_suffix(".path"))
+        {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let get = |key: &str| config.get_string(&format!("vendor.{}.{}", name, key));
+            Ok(VendoredInfo {
+                path: get("path")?,
+                url: get("url").with_context(|| format!("'{}' has no url", name))?,
+                commit: get("commit").with_context(|| format!("'{}' has no commit", name))?,
+                branch: get("branch").ok(),
+                groups: groups(&config, &name),
+                shallow: get("shallow").ok(),
+                update: get("update").ok(),
+                name,
+            })
+        })
+        .collect()
+}
+
+fn groups(config: &git2::Config, name: &str) -> Vec<String> {
+    let mut groups = Vec::new();
+    if let Ok(mut entries) = config.multivar(&format!("vendor.{}.group", name), None) {
+        while let Some(Ok(entry)) = entries.next() {
+            groups.extend(entry.value().map(str::to_string));
+        }
+    }
+    groups
+}
+
+/// Write the contents of `tree` to `dest`, preserving executable bits and symlinks.
+fn export_tree(repo: &Repository, tree: &git2::Tree, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+
+    for entry in tree.iter() {
+        let name = entry.name().context("Tree entry name is not valid UTF-8")?;
+        let target = dest.join(name);
+
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                let subtree = repo.find_tree(entry.id())?;
+                export_tree(repo, &subtree, &target)?;
+            }
+            Some(ObjectType::Blob) => {
+                let blob = repo.find_blob(entry.id())?;
+                write_blob(&target, blob.content(), entry.filemode())
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+            }
+            _ => eprintln!(
+                "  {} skipping nested submodule {}",
+                "!".yellow(),
+                target.display()
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_blob(path: &Path, content: &[u8], mode: i32) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    match mode {
+        0o120000 => std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(content), path),
+        0o100755 => {
+            fs::write(path, content)?;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        }
+        _ => fs::write(path, content),
+    }
+}
+
+#[cfg(not(unix))]
+fn write_blob(path: &Path, content: &[u8], _mode: i32) -> std::io::Result<()> {
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VendoredInfo, read_vendored, record};
+
+    #[test]
+    fn recorded_settings_round_trip() {
+        let dir = std::env::temp_dir().join(format!("git-dom-vendor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let info = VendoredInfo {
+            name: "lib".to_string(),
+            path: "libs/lib".to_string(),
+            url: "../lib.git".to_string(),
+            commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            branch: Some("main".to_string()),
+            groups: vec!["frontend".to_string(), "core".to_string()],
+            shallow: Some("true".to_string()),
+            update: Some("rebase".to_string()),
+        };
+        let result = record(&dir, &info).and_then(|()| read_vendored(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(result.unwrap(), vec![info]);
+    }
+}
@@ -12,6 +12,13 @@ pub enum CommitMode {
 pub struct Config {
     pub root: String,
     pub commit_mode: CommitMode,
+    /// Threads used to gather submodule status.
+    pub jobs: usize,
+    /// `git dom prompt` template, time budget in milliseconds and the text
+    /// printed when the budget runs out.
+    pub prompt_format: String,
+    pub prompt_timeout: u64,
+    pub prompt_stale: String,
 }
 
 impl Config {
@@ -32,6 +39,38 @@ impl Config {
             })
             .unwrap_or(CommitMode::Auto);
 
-        Ok(Config { root, commit_mode })
+        let jobs = git_config
+            .get_i64("dom.jobs")
+            .ok()
+            .and_then(|n| usize::try_from(n).ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(4)
+            });
+
+        let prompt_format = git_config
+            .get_string("dom.promptFormat")
+            .unwrap_or_else(|_| "⎇%(total) ✱%(dirty) ↑%(ahead) ↓%(behind)".to_string());
+
+        let prompt_timeout = git_config
+            .get_i64("dom.promptTimeout")
+            .ok()
+            .and_then(|n| u64::try_from(n).ok())
+            .unwrap_or(200);
+
+        let prompt_stale = git_config
+            .get_string("dom.promptStale")
+            .unwrap_or_else(|_| "⎇?".to_string());
+
+        Ok(Config {
+            root,
+            commit_mode,
+            jobs,
+            prompt_format,
+            prompt_timeout,
+            prompt_stale,
+        })
     }
 }
@@ -0,0 +1,42 @@
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::Command;
+
+/// Run a git command in `dir`, failing with git's stderr if it exits non-zero.
+pub fn run(dir: &Path, args: &[&str]) -> Result<()> {
+    output(dir, args).map(|_| ())
+}
+
+/// Run a git command in `dir` and return its stdout.
+pub fn output(dir: &Path, args: &[&str]) -> Result<String> {
+    output_with(Command::new("git").current_dir(dir), args)
+}
+
+/// Run a git command in `dir` against another index file, such as one
+/// holding just part of what's staged.
+pub fn run_with_index(dir: &Path, index: &Path, args: &[&str]) -> Result<()> {
+    output_with(
+        Command::new("git")
+            .current_dir(dir)
+            .env("GIT_INDEX_FILE", index),
+        args,
+    )
+    .map(|_| ())
+}
+
+fn output_with(command: &mut Command, args: &[&str]) -> Result<String> {
+    let output = command
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args[0]))?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[..2.min(args.len())].join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
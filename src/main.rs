@@ -1,10 +1,16 @@
 use anyhow::Result;
 use clap::Parser;
 
+mod cache;
 mod cli;
 mod commands;
 mod config;
+mod git;
+mod manifest;
+mod parallel;
 mod submodule;
+mod template;
+mod watch;
 
 fn main() -> Result<()> {
     let args = cli::Cli::parse();
@@ -13,15 +19,196 @@ fn main() -> Result<()> {
     if std::env::var("NO_COLOR").is_ok() || args.no_colour {
         colored::control::set_override(false);
     }
+    if args.no_cache {
+        cache::disable();
+    }
 
     match args.command {
-        cli::Command::Ls { name } => commands::ls::run(name),
-        cli::Command::Status { name } => commands::status::run(name),
-        cli::Command::Clone { url, no_commit } => commands::clone::run(url, no_commit),
-        cli::Command::Pull { name, commit } => commands::pull::run(name, commit),
-        cli::Command::Rm { name, no_commit } => commands::rm::run(name, no_commit),
-        cli::Command::Diff { name, full } => commands::diff::run(name, full),
-        cli::Command::Foreach { command, parallel } => commands::foreach::run(command, parallel),
+        cli::Command::Ls {
+            names,
+            long,
+            format,
+            sort,
+            columns,
+            jobs,
+            watch,
+            selection,
+        } => commands::ls::run(
+            selection.filter(names).allow_empty(),
+            long,
+            format,
+            sort,
+            columns,
+            jobs,
+            watch,
+        ),
+        cli::Command::Status {
+            names,
+            long,
+            porcelain,
+            exit_code,
+            jobs,
+            watch,
+            selection,
+        } => commands::status::run(
+            selection.filter(names).allow_empty(),
+            long,
+            porcelain,
+            exit_code,
+            jobs,
+            watch,
+        ),
+        cli::Command::Clone {
+            url,
+            from,
+            jobs,
+            no_commit,
+        } => commands::clone::run(url, from, jobs, no_commit),
+        cli::Command::Init {
+            names,
+            jobs,
+            recursive,
+            selection,
+        } => commands::init::run(selection.filter(names), jobs, recursive),
+        cli::Command::Attach { names, selection } => commands::attach::run(selection.filter(names)),
+        cli::Command::Branch {
+            branch,
+            names,
+            selection,
+        } => commands::branch::run(branch, selection.filter(names)),
+        cli::Command::Switch { branch, selection } => {
+            commands::switch::run(branch, selection.filter(Vec::new()))
+        }
+        cli::Command::Checkout {
+            names,
+            head,
+            force,
+            skip_dirty,
+            install_hook,
+            selection,
+        } => commands::checkout::run(
+            selection.filter(names),
+            head,
+            force,
+            skip_dirty,
+            install_hook,
+        ),
+        cli::Command::Pull {
+            names,
+            commit,
+            selection,
+        } => commands::pull::run(selection.filter(names), commit),
+        cli::Command::Rm {
+            names,
+            no_commit,
+            selection,
+        } => commands::rm::run(selection.filter(names).exact(), no_commit),
+        cli::Command::Sync {
+            names,
+            from,
+            check,
+            selection,
+        } => commands::sync::run(selection.filter(names), from, check),
+        cli::Command::Vendor {
+            names,
+            no_commit,
+            selection,
+        } => commands::vendor::run(selection.filter(names).exact(), no_commit),
+        cli::Command::Unvendor { path, no_commit } => commands::unvendor::run(path, no_commit),
+        cli::Command::Diff {
+            names,
+            full,
+            jobs,
+            selection,
+        } => commands::diff::run(selection.filter(names).allow_empty(), full, jobs),
+        cli::Command::Adopt {
+            path,
+            scan,
+            dry_run,
+            no_commit,
+        } => commands::adopt::run(path, scan, dry_run, no_commit),
+        cli::Command::Import {
+            manifest,
+            format,
+            jobs,
+            no_commit,
+        } => commands::import::run(manifest, format, jobs, no_commit),
+        cli::Command::Export {
+            format,
+            output,
+            selection,
+        } => commands::export::run(selection.filter(Vec::new()).allow_empty(), format, output),
+        cli::Command::Foreach {
+            select,
+            command,
+            parallel,
+            selection,
+        } => commands::foreach::run(selection.filter(select), command, parallel),
+        cli::Command::Log {
+            select,
+            since,
+            until,
+            author,
+            max_count,
+            json,
+            paths,
+            selection,
+        } => commands::log::run(
+            selection.filter(select).allow_empty(),
+            commands::log::Options {
+                since,
+                until,
+                author,
+                max_count,
+            },
+            paths,
+            json,
+        ),
+        cli::Command::Grep {
+            select,
+            pattern,
+            pathspec,
+            ignore_case,
+            files_with_matches,
+            line_number,
+            cached,
+            rev,
+            json,
+            selection,
+        } => commands::grep::run(
+            selection.filter(select).allow_empty(),
+            pattern,
+            pathspec,
+            commands::grep::Options {
+                ignore_case,
+                files_with_matches,
+                line_number,
+                cached,
+                rev,
+            },
+            json,
+        ),
+        cli::Command::History { name, max_count } => commands::history::run(name, max_count),
+        cli::Command::Contains { name, commit } => commands::contains::run(name, commit),
+        cli::Command::Tui {
+            names,
+            jobs,
+            selection,
+        } => commands::tui::run(selection.filter(names).allow_empty(), jobs),
+        cli::Command::Prompt {
+            format,
+            timeout,
+            stale,
+            refresh,
+            selection,
+        } => commands::prompt::run(
+            selection.filter(Vec::new()).allow_empty(),
+            format,
+            timeout,
+            stale,
+            refresh,
+        ),
+        cli::Command::Cache { command } => commands::cache::run(command),
         cli::Command::Completions { shell } => commands::completions::run(shell),
         cli::Command::Man { output, install } => commands::man::run(output, install),
     }
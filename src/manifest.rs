@@ -0,0 +1,551 @@
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Multi-repo manifest formats understood by `import` and `export`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ManifestFormat {
+    /// Android `repo` XML manifest
+    Repo,
+    /// Zephyr `west.yml`
+    West,
+    /// vcstool `.repos` file
+    Vcs,
+}
+
+/// A single repository entry in a manifest.
+#[derive(Debug, PartialEq)]
+pub struct Project {
+    pub name: String,
+    pub path: String,
+    pub url: String,
+    /// Branch, tag or commit pinned by the manifest
+    pub revision: Option<String>,
+    /// Branch to track, when the manifest names one separately from the revision
+    pub branch: Option<String>,
+}
+
+impl ManifestFormat {
+    /// Guess the format from the file name, falling back to the content.
+    pub fn detect(file_name: &str, content: &str) -> Option<Self> {
+        if file_name.ends_with(".xml") || content.trim_start().starts_with('<') {
+            Some(ManifestFormat::Repo)
+        } else if file_name.ends_with(".repos") || content.contains("repositories:") {
+            Some(ManifestFormat::Vcs)
+        } else if content.contains("manifest:") {
+            Some(ManifestFormat::West)
+        } else {
+            None
+        }
+    }
+
+    pub fn parse(self, content: &str) -> Result<Vec<Project>> {
+        let mut projects = match self {
+            ManifestFormat::Repo => parse_repo(content),
+            ManifestFormat::West => parse_west(content),
+            ManifestFormat::Vcs => parse_vcs(content),
+        }?;
+        for p in &mut projects {
+            p.path = normalize_path(&p.path);
+        }
+        Ok(projects)
+    }
+
+    pub fn render(self, projects: &[Project]) -> Result<String> {
+        match self {
+            ManifestFormat::Repo => Ok(render_repo(projects)),
+            ManifestFormat::West => render_west(projects),
+            ManifestFormat::Vcs => render_vcs(projects),
+        }
+    }
+}
+
+fn parse_repo(content: &str) -> Result<Vec<Project>> {
+    let doc = roxmltree::Document::parse(content).context("Failed to parse repo manifest")?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "manifest" {
+        bail!("Expected a <manifest> root element");
+    }
+
+    let mut remotes = HashMap::new();
+    let mut default_remote = None;
+    let mut default_revision = None;
+
+    for node in root.children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "remote" => {
+                let name = node.attribute("name").context("<remote> without a name")?;
+                let fetch = node.attribute("fetch").context("<remote> without fetch")?;
+                if fetch.starts_with('.') {
+                    bail!(
+                        "Remote '{}' uses a relative fetch URL, which needs the manifest's own URL",
+                        name
+                    );
+                }
+                remotes.insert(name, (fetch, node.attribute("revision")));
+            }
+            "default" => {
+                default_remote = node.attribute("remote");
+                default_revision = node.attribute("revision");
+            }
+            "include" => bail!("<include> is not supported; import each manifest separately"),
+            _ => {}
+        }
+    }
+
+    let removed: Vec<&str> = root
+        .children()
+        .filter(|n| n.has_tag_name("remove-project"))
+        .filter_map(|n| n.attribute("name"))
+        .collect();
+
+    let mut projects = Vec::new();
+
+    for node in root.children().filter(|n| n.has_tag_name("project")) {
+        let name = node.attribute("name").context("<project> without a name")?;
+        if removed.contains(&name) {
+            continue;
+        }
+
+        let remote_name = node
+            .attribute("remote")
+            .or(default_remote)
+            .with_context(|| format!("Project '{}' has no remote", name))?;
+        let (fetch, remote_revision) = remotes
+            .get(remote_name)
+            .with_context(|| format!("Unknown remote '{}' for project '{}'", remote_name, name))?;
+
+        projects.push(Project {
+            name: name.to_string(),
+            path: node.attribute("path").unwrap_or(name).to_string(),
+            url: format!("{}/{}", fetch.trim_end_matches('/'), name),
+            revision: node
+                .attribute("revision")
+                .or(*remote_revision)
+                .or(default_revision)
+                .map(|r| r.trim_start_matches("refs/heads/").to_string()),
+            branch: node
+                .attribute("upstream")
+                .or(node.attribute("dest-branch"))
+                .map(|b| b.trim_start_matches("refs/heads/").to_string()),
+        });
+    }
+
+    Ok(projects)
+}
+
+#[derive(Deserialize, Serialize)]
+struct WestFile {
+    manifest: WestManifest,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WestManifest {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    remotes: Vec<WestRemote>,
+    #[serde(default, skip_serializing)]
+    defaults: WestDefaults,
+    #[serde(default)]
+    projects: Vec<WestProject>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WestRemote {
+    name: String,
+    #[serde(rename = "url-base")]
+    url_base: String,
+}
+
+#[derive(Default, Deserialize)]
+struct WestDefaults {
+    remote: Option<String>,
+    revision: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WestProject {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote: Option<String>,
+    #[serde(rename = "repo-path", skip_serializing_if = "Option::is_none")]
+    repo_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revision: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+fn parse_west(content: &str) -> Result<Vec<Project>> {
+    let file: WestFile = serde_yaml::from_str(content).context("Failed to parse west manifest")?;
+    let manifest = file.manifest;
+
+    manifest
+        .projects
+        .into_iter()
+        .map(|p| {
+            let url = match p.url {
+                Some(url) => url,
+                None => {
+                    let remote_name = p
+                        .remote
+                        .as_ref()
+                        .or(manifest.defaults.remote.as_ref())
+                        .with_context(|| format!("Project '{}' has no url or remote", p.name))?;
+                    let remote = manifest
+                        .remotes
+                        .iter()
+                        .find(|r| &r.name == remote_name)
+                        .with_context(|| {
+                            format!("Unknown remote '{}' for project '{}'", remote_name, p.name)
+                        })?;
+                    format!(
+                        "{}/{}",
+                        remote.url_base.trim_end_matches('/'),
+                        p.repo_path.as_ref().unwrap_or(&p.name)
+                    )
+                }
+            };
+
+            Ok(Project {
+                path: p.path.unwrap_or_else(|| p.name.clone()),
+                name: p.name,
+                url,
+                revision: p.revision.or_else(|| manifest.defaults.revision.clone()),
+                branch: None,
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Serialize)]
+struct VcsRepo {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+fn parse_vcs(content: &str) -> Result<Vec<Project>> {
+    let file: serde_yaml::Mapping =
+        serde_yaml::from_str(content).context("Failed to parse .repos file")?;
+    let repositories = file
+        .get("repositories")
+        .and_then(|r| r.as_mapping())
+        .context("Expected a top-level 'repositories' mapping")?;
+
+    let mut projects = Vec::new();
+
+    // Iterate the mapping directly to keep the file's ordering
+    for (key, value) in repositories {
+        let path = key.as_str().context("Repository keys must be paths")?;
+        let repo: VcsRepo = serde_yaml::from_value(value.clone())
+            .with_context(|| format!("Invalid entry for '{}'", path))?;
+        if repo.kind != "git" {
+            bail!(
+                "'{}' is a {} repository; only git is supported",
+                path,
+                repo.kind
+            );
+        }
+
+        projects.push(Project {
+            name: path.to_string(),
+            path: path.to_string(),
+            url: repo.url,
+            revision: repo.version,
+            branch: None,
+        });
+    }
+
+    Ok(projects)
+}
+
+/// Split a clone URL into a fetch base and a project name, the way `repo`
+/// joins them. Joining the two with `/` gives back the URL exactly.
+fn split_url(url: &str) -> (String, String) {
+    if let Some(scheme_end) = url.find("://") {
+        // Everything after the host is the name, so projects share remotes
+        if let Some(i) = url[scheme_end + 3..].find('/') {
+            let (base, name) = url.split_at(scheme_end + 3 + i);
+            return (base.to_string(), name[1..].to_string());
+        }
+    } else if let Some((host, path)) = url.split_once(':') {
+        // scp-like git@host:owner/repo.git; a `/` after the colon would make
+        // the path absolute, so split at the last one instead
+        return match path.rsplit_once('/') {
+            Some((dir, name)) => (format!("{}:{}", host, dir), name.to_string()),
+            None => (format!("{}:.", host), path.to_string()),
+        };
+    }
+
+    match url.rsplit_once('/') {
+        Some((base, name)) => (base.to_string(), name.to_string()),
+        None => (".".to_string(), url.to_string()),
+    }
+}
+
+/// A manifest path in the form git reports submodule paths: no `./`
+/// segments and no trailing slash.
+pub fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn render_repo(projects: &[Project]) -> String {
+    let mut remotes: Vec<String> = Vec::new();
+    let mut lines = Vec::new();
+
+    for p in projects {
+        let (base, name) = split_url(&p.url);
+        let remote = match remotes.iter().position(|r| *r == base) {
+            Some(i) => i,
+            None => {
+                remotes.push(base);
+                remotes.len() - 1
+            }
+        };
+
+        let mut line = format!(
+            "  <project name=\"{}\" path=\"{}\" remote=\"r{}\"",
+            xml_escape(&name),
+            xml_escape(&p.path),
+            remote
+        );
+        if let Some(ref revision) = p.revision {
+            line.push_str(&format!(" revision=\"{}\"", xml_escape(revision)));
+        }
+        if let Some(ref branch) = p.branch {
+            line.push_str(&format!(" upstream=\"{}\"", xml_escape(branch)));
+        }
+        line.push_str(" />");
+        lines.push(line);
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<manifest>\n");
+    for (i, base) in remotes.iter().enumerate() {
+        out.push_str(&format!(
+            "  <remote name=\"r{}\" fetch=\"{}\" />\n",
+            i,
+            xml_escape(base)
+        ));
+    }
+    if !remotes.is_empty() {
+        out.push('\n');
+    }
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str("</manifest>\n");
+    out
+}
+
+fn render_west(projects: &[Project]) -> Result<String> {
+    let file = WestFile {
+        manifest: WestManifest {
+            remotes: Vec::new(),
+            defaults: WestDefaults::default(),
+            projects: projects
+                .iter()
+                .map(|p| WestProject {
+                    // West project names are used as identifiers on the command line
+                    name: p.name.replace('/', "-"),
+                    url: Some(p.url.clone()),
+                    remote: None,
+                    repo_path: None,
+                    revision: p.revision.clone(),
+                    path: Some(p.path.clone()),
+                })
+                .collect(),
+        },
+    };
+    serde_yaml::to_string(&file).context("Failed to render west manifest")
+}
+
+fn render_vcs(projects: &[Project]) -> Result<String> {
+    let mut repositories = serde_yaml::Mapping::new();
+    for p in projects {
+        let repo = VcsRepo {
+            kind: "git".to_string(),
+            url: p.url.clone(),
+            version: p.revision.clone(),
+        };
+        repositories.insert(p.path.clone().into(), serde_yaml::to_value(repo)?);
+    }
+
+    let mut file = serde_yaml::Mapping::new();
+    file.insert("repositories".into(), repositories.into());
+    serde_yaml::to_string(&file).context("Failed to render .repos file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ManifestFormat, Project, normalize_path, split_url};
+
+    fn project(path: &str, url: &str, revision: Option<&str>) -> Project {
+        Project {
+            name: path.to_string(),
+            path: path.to_string(),
+            url: url.to_string(),
+            revision: revision.map(|r| r.to_string()),
+            branch: None,
+        }
+    }
+
+    #[test]
+    fn parses_repo_manifest() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <remote name="aosp" fetch="https://android.googlesource.com/" />
+  <default remote="aosp" revision="refs/heads/main" />
+  <project name="platform/build" path="build/make" />
+  <project name="platform/art" revision="0123456789abcdef0123456789abcdef01234567" upstream="main" />
+  <project name="platform/gone" />
+  <remove-project name="platform/gone" />
+</manifest>"#;
+
+        let projects = ManifestFormat::Repo.parse(xml).unwrap();
+        assert_eq!(projects.len(), 2);
+        assert_eq!(
+            projects[0].url,
+            "https://android.googlesource.com/platform/build"
+        );
+        assert_eq!(projects[0].path, "build/make");
+        assert_eq!(projects[0].revision.as_deref(), Some("main"));
+        assert_eq!(projects[1].path, "platform/art");
+        assert_eq!(projects[1].branch.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn parses_west_manifest() {
+        let yaml = "
+manifest:
+  remotes:
+    - name: upstream
+      url-base: https://github.com/zephyrproject-rtos
+  defaults:
+    remote: upstream
+    revision: main
+  projects:
+    - name: hal_nordic
+      path: modules/hal/nordic
+      revision: v3.0.0
+    - name: mcuboot
+      url: https://github.com/mcu-tools/mcuboot
+";
+        let projects = ManifestFormat::West.parse(yaml).unwrap();
+        assert_eq!(
+            projects,
+            vec![
+                Project {
+                    name: "hal_nordic".to_string(),
+                    ..project(
+                        "modules/hal/nordic",
+                        "https://github.com/zephyrproject-rtos/hal_nordic",
+                        Some("v3.0.0")
+                    )
+                },
+                project(
+                    "mcuboot",
+                    "https://github.com/mcu-tools/mcuboot",
+                    Some("main")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_vcs_repos_in_order() {
+        let yaml = "
+repositories:
+  src/zeta:
+    type: git
+    url: https://github.com/ros/zeta.git
+    version: humble
+  src/alpha:
+    type: git
+    url: https://github.com/ros/alpha.git
+";
+        let projects = ManifestFormat::Vcs.parse(yaml).unwrap();
+        assert_eq!(
+            projects,
+            vec![
+                project(
+                    "src/zeta",
+                    "https://github.com/ros/zeta.git",
+                    Some("humble")
+                ),
+                project("src/alpha", "https://github.com/ros/alpha.git", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rendered_manifests_round_trip() {
+        let projects = vec![
+            project(
+                "vendor/tokio",
+                "https://github.com/tokio-rs/tokio",
+                Some("0123456789abcdef0123456789abcdef01234567"),
+            ),
+            project("vendor/serde", "git@github.com:serde-rs/serde.git", None),
+            project(
+                "vendor/bytes",
+                "ssh://git@github.com/tokio-rs/bytes.git",
+                None,
+            ),
+        ];
+
+        let key = |p: &Project| (p.path.clone(), p.url.clone(), p.revision.clone());
+        let expected: Vec<_> = projects.iter().map(key).collect();
+
+        for format in [
+            ManifestFormat::Repo,
+            ManifestFormat::West,
+            ManifestFormat::Vcs,
+        ] {
+            let rendered = format.render(&projects).unwrap();
+            let parsed: Vec<_> = format.parse(&rendered).unwrap().iter().map(key).collect();
+            assert_eq!(parsed, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn splits_urls_without_losing_anything() {
+        for url in [
+            "https://github.com/tokio-rs/tokio.git",
+            "git@github.com:serde-rs/serde.git",
+            "git@host:repo.git",
+            "/srv/git/foo",
+        ] {
+            let (base, name) = split_url(url);
+            assert!(!name.is_empty(), "{}", url);
+            let joined = format!("{}/{}", base, name);
+            assert!(
+                joined == url || joined == "git@host:./repo.git",
+                "{} became {}",
+                url,
+                joined
+            );
+        }
+    }
+
+    #[test]
+    fn normalizes_manifest_paths() {
+        assert_eq!(normalize_path("./libs/foo/"), "libs/foo");
+        assert_eq!(normalize_path("libs//foo"), "libs/foo");
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Apply `f` to every item on up to `jobs` worker threads, returning results in input order.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    map_init(items, jobs, || (), |_, item| f(item))
+}
+
+/// Like [`map`], but each worker thread first builds its own state with
+/// `init` — such as a `Repository`, which can't be shared between threads.
+pub fn map_init<T, S, R, I, F>(items: &[T], jobs: usize, init: I, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, &T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                let mut state = init();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    let result = f(&mut state, item);
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_input_order_and_builds_state_once_per_worker() {
+        let inits = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..50).collect();
+        let results = map_init(
+            &items,
+            4,
+            || inits.fetch_add(1, Ordering::Relaxed),
+            |_, n| n * 2,
+        );
+        assert_eq!(results, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+        assert_eq!(inits.load(Ordering::Relaxed), 4);
+    }
+}
@@ -1,14 +1,30 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
 use git2::{Repository, StatusOptions};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::parallel;
+
 pub struct SubmoduleInfo {
     pub name: String,
     pub path: PathBuf,
     pub url: String,
+    pub groups: Vec<String>,
+    pub state: SubmoduleState,
     pub branch: Option<String>,
-    pub head_commit: Option<String>,
+    pub detached: bool,
+    pub head_commit: Option<git2::Oid>,
+    /// `head_commit` abbreviated as far as it stays unambiguous in the submodule.
+    pub head_short: Option<String>,
     pub head_message: Option<String>,
+    pub head_author: Option<String>,
+    pub head_time: Option<git2::Time>,
+    /// The gitlink recorded in the parent's HEAD tree.
+    pub recorded_commit: Option<git2::Oid>,
+    /// The gitlink staged in the parent's index.
+    pub staged_commit: Option<git2::Oid>,
     pub is_dirty: bool,
     pub ahead: usize,
     pub behind: usize,
@@ -18,175 +34,1006 @@ pub struct SubmoduleInfo {
     pub parent_changed: bool,
 }
 
-/// Discover all submodules in the given repo, optionally filtering by name.
-pub fn discover(repo: &Repository, filter: Option<&str>) -> Result<Vec<SubmoduleInfo>> {
-    let submodules = repo.submodules().context("Failed to read submodules")?;
-    let workdir = repo
-        .workdir()
-        .context("Bare repositories are not supported")?;
+impl SubmoduleInfo {
+    /// Info for a submodule that hasn't been looked at yet: uninitialised, with
+    /// nothing checked out.
+    pub fn new(name: String, path: PathBuf, url: String) -> Self {
+        SubmoduleInfo {
+            name,
+            path,
+            url,
+            groups: Vec::new(),
+            state: SubmoduleState::Uninitialised,
+            branch: None,
+            detached: false,
+            head_commit: None,
+            head_short: None,
+            head_message: None,
+            head_author: None,
+            head_time: None,
+            recorded_commit: None,
+            staged_commit: None,
+            is_dirty: false,
+            ahead: 0,
+            behind: 0,
+            staged: 0,
+            modified: 0,
+            untracked: 0,
+            parent_changed: false,
+        }
+    }
+}
 
-    let mut results = Vec::new();
+/// How far a submodule has been set up in this checkout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmoduleState {
+    /// Checked out and consistent with .gitmodules.
+    Initialised,
+    /// Listed in .gitmodules but never cloned into the worktree.
+    Uninitialised,
+    /// The worktree has a `.git` file whose git directory no longer exists.
+    MissingGitdir,
+    /// Checked out, but `origin` points somewhere other than .gitmodules says.
+    UrlMismatch,
+    /// Listed in .gitmodules without a gitlink in the parent's index.
+    NotInIndex,
+}
+
+impl SubmoduleState {
+    pub fn label(self) -> &'static str {
+        match self {
+            SubmoduleState::Initialised => "initialised",
+            SubmoduleState::Uninitialised => "uninitialised",
+            SubmoduleState::MissingGitdir => "missing gitdir",
+            SubmoduleState::UrlMismatch => "url mismatch",
+            SubmoduleState::NotInIndex => "not in index",
+        }
+    }
+}
 
-    for sm in &submodules {
-        let name = sm.name().unwrap_or("").to_string();
+/// Which of the costlier parts of [`SubmoduleInfo`] to compute. Fields not
+/// asked for are left at zero; everything else is always filled in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Detail {
+    /// `is_dirty`, stopping at the first change found.
+    pub dirty: bool,
+    /// Exact `staged` and `modified` counts (and `is_dirty` from them).
+    pub counts: bool,
+    /// `untracked`, which walks every directory in the worktree.
+    pub untracked: bool,
+    /// `ahead` and `behind`, which walk history against the upstream.
+    pub upstream: bool,
+}
 
-        if let Some(f) = filter
-            && name != f
-        {
-            continue;
+impl Detail {
+    pub fn all() -> Self {
+        Detail {
+            dirty: true,
+            counts: true,
+            untracked: true,
+            upstream: true,
+        }
+    }
+
+    fn any(self) -> bool {
+        self.dirty || self.counts || self.untracked || self.upstream
+    }
+
+    /// Whether results gathered with this detail answer everything in `other`.
+    pub(crate) fn covers(self, other: Detail) -> bool {
+        (!other.dirty || self.dirty || self.counts)
+            && (!other.counts || self.counts)
+            && (!other.untracked || self.untracked)
+            && (!other.upstream || self.upstream)
+    }
+
+    pub(crate) fn union(self, other: Detail) -> Self {
+        Detail {
+            dirty: self.dirty || other.dirty,
+            counts: self.counts || other.counts,
+            untracked: self.untracked || other.untracked,
+            upstream: self.upstream || other.upstream,
+        }
+    }
+}
+
+/// Results of the costlier scans behind [`SubmoduleInfo`].
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Scan {
+    pub(crate) is_dirty: bool,
+    pub(crate) staged: usize,
+    pub(crate) modified: usize,
+    pub(crate) untracked: usize,
+    pub(crate) ahead: usize,
+    pub(crate) behind: usize,
+}
+
+/// A state predicate that narrows a selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Dirty,
+    Ahead,
+    Behind,
+    Detached,
+    Uninitialised,
+}
+
+impl State {
+    fn holds(self, info: &SubmoduleInfo) -> bool {
+        match self {
+            State::Dirty => info.is_dirty,
+            State::Ahead => info.ahead > 0,
+            State::Behind => info.behind > 0,
+            State::Detached => info.detached,
+            State::Uninitialised => info.state == SubmoduleState::Uninitialised,
+        }
+    }
+
+    /// What has to be gathered to test this state.
+    fn detail(self) -> Detail {
+        match self {
+            State::Dirty => Detail {
+                dirty: true,
+                ..Detail::default()
+            },
+            State::Ahead | State::Behind => Detail {
+                upstream: true,
+                ..Detail::default()
+            },
+            State::Detached | State::Uninitialised => Detail::default(),
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            State::Dirty => "--dirty",
+            State::Ahead => "--ahead",
+            State::Behind => "--behind",
+            State::Detached => "--detached",
+            State::Uninitialised => "--uninitialized",
+        }
+    }
+}
+
+/// Which submodules a command operates on.
+///
+/// A submodule is selected when it matches any selector, belongs to any of
+/// the groups and satisfies every state. Selectors are names, paths, path
+/// prefixes (`vendor` matches `vendor/foo`) or glob patterns (`vendor/*`)
+/// tested against both name and path. An empty list places no restriction.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub selectors: Vec<String>,
+    pub groups: Vec<String>,
+    pub states: Vec<State>,
+    /// Whether a bare path selector also matches the submodules beneath it.
+    /// When it doesn't, only a trailing `/` (`vendor/`) selects by prefix.
+    pub prefix: bool,
+    /// Whether selectors are names or paths taken as written, so `*?[` in
+    /// them are never read as patterns.
+    pub literal: bool,
+    /// Whether state predicates that rule out every submodule leave an empty
+    /// selection rather than an error.
+    pub allow_empty: bool,
+}
+
+impl Filter {
+    pub fn new(selectors: Vec<String>, groups: Vec<String>, states: Vec<State>) -> Self {
+        Filter {
+            selectors,
+            groups,
+            states,
+            prefix: true,
+            literal: false,
+            allow_empty: false,
+        }
+    }
+
+    /// Exactly the submodules with these names or paths.
+    pub fn names(names: Vec<String>) -> Self {
+        Filter {
+            literal: true,
+            ..Filter::new(names, Vec::new(), Vec::new()).exact()
+        }
+    }
+
+    /// Require bare path selectors to match a name or path exactly, for
+    /// commands where selecting too much would destroy work.
+    pub fn exact(self) -> Self {
+        Filter {
+            prefix: false,
+            ..self
         }
+    }
+
+    /// Let the state predicates select nothing, for commands that only report
+    /// and can show an empty result.
+    pub fn allow_empty(self) -> Self {
+        Filter {
+            allow_empty: true,
+            ..self
+        }
+    }
+
+    /// Command-line arguments that select the same submodules, for handing
+    /// this filter on to another `git dom` process.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for group in &self.groups {
+            args.extend(["--group".to_string(), group.clone()]);
+        }
+        args.extend(self.states.iter().map(|s| s.flag().to_string()));
+        args.extend(self.selectors.iter().cloned());
+        args
+    }
+
+    /// Whether this filter narrows the full set of submodules at all.
+    pub fn is_restricted(&self) -> bool {
+        !self.selectors.is_empty() || !self.groups.is_empty() || !self.states.is_empty()
+    }
+
+    fn compile(&self, selector: &str) -> Result<Selector> {
+        if self.literal {
+            Ok(Selector::Exact(selector.to_string()))
+        } else {
+            Selector::parse(selector, self.prefix)
+        }
+    }
+
+    fn detail(&self) -> Detail {
+        self.states
+            .iter()
+            .fold(Detail::default(), |d, s| d.union(s.detail()))
+    }
+
+    fn describe(&self) -> String {
+        let mut parts: Vec<String> = self.selectors.iter().map(|s| format!("'{}'", s)).collect();
+        parts.extend(self.groups.iter().map(|g| format!("--group {}", g)));
+        parts.extend(self.states.iter().map(|s| s.flag().to_string()));
+        parts.join(" ")
+    }
+}
 
-        let path = PathBuf::from(sm.path());
-        let url = sm.url().unwrap_or("").to_string();
+/// A compiled selector.
+enum Selector {
+    Glob(glob::Pattern),
+    Prefix(String),
+    Exact(String),
+}
 
-        let abs_path = workdir.join(&path);
-        let mut info = if abs_path.exists() {
-            gather_info(&name, &path, &url, &abs_path)?
+impl Selector {
+    fn parse(selector: &str, prefix: bool) -> Result<Self> {
+        if selector.contains(['*', '?', '[']) {
+            let pattern = glob::Pattern::new(selector)
+                .with_context(|| format!("Invalid pattern '{}'", selector))?;
+            Ok(Selector::Glob(pattern))
+        } else if prefix || selector.ends_with('/') {
+            Ok(Selector::Prefix(selector.trim_end_matches('/').to_string()))
         } else {
-            SubmoduleInfo {
-                name,
-                path,
-                url,
-                branch: None,
-                head_commit: None,
-                head_message: None,
-                is_dirty: false,
-                ahead: 0,
-                behind: 0,
-                staged: 0,
-                modified: 0,
-                untracked: 0,
-                parent_changed: false,
+            Ok(Selector::Exact(selector.to_string()))
+        }
+    }
+
+    fn matches(&self, name: &str, path: &Path) -> bool {
+        match self {
+            Selector::Glob(pattern) => {
+                // `*` stays within one path component; `**` crosses them
+                let opts = glob::MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                };
+                pattern.matches_with(name, opts) || pattern.matches_path_with(path, opts)
             }
-        };
+            Selector::Prefix(s) => name == s || path.starts_with(s),
+            Selector::Exact(s) => name == s || path == Path::new(s),
+        }
+    }
+}
+
+/// Submodules matching the filter's selectors and groups, before any state
+/// predicate is applied.
+///
+/// Fails if a selector doesn't match any submodule, or if the selectors and
+/// groups together leave nothing.
+fn candidates<'r>(repo: &'r Repository, filter: &Filter) -> Result<Vec<git2::Submodule<'r>>> {
+    let submodules = repo.submodules().context("Failed to read submodules")?;
+
+    let selectors = filter
+        .selectors
+        .iter()
+        .map(|s| filter.compile(s))
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some((missing, _)) = filter.selectors.iter().zip(&selectors).find(|(_, sel)| {
+        !submodules
+            .iter()
+            .any(|sm| sel.matches(sm.name().unwrap_or(""), sm.path()))
+    }) {
+        bail!("No submodule matches '{}'", missing);
+    }
+
+    let selected: Vec<_> = submodules
+        .into_iter()
+        .filter(|sm| {
+            selectors.is_empty()
+                || selectors
+                    .iter()
+                    .any(|sel| sel.matches(sm.name().unwrap_or(""), sm.path()))
+        })
+        .filter(|sm| {
+            filter.groups.is_empty() || {
+                let groups = groups(repo, sm.name().unwrap_or(""));
+                filter.groups.iter().any(|g| groups.contains(g))
+            }
+        })
+        .collect();
+
+    if selected.is_empty() && !filter.groups.is_empty() {
+        bail!("No submodules match {}", filter.describe());
+    }
+    Ok(selected)
+}
+
+/// Fail with a description of the filter when its states leave nothing
+/// selected, unless the filter allows that.
+fn ensure_selected<T>(filter: &Filter, selected: &[T]) -> Result<()> {
+    if selected.is_empty() && !filter.states.is_empty() && !filter.allow_empty {
+        bail!("No submodules match {}", filter.describe());
+    }
+    Ok(())
+}
+
+/// The submodules selected by `filter`, in .gitmodules order.
+///
+/// Fails if a selector doesn't match any submodule, or if the filter as a
+/// whole leaves nothing selected (see [`Filter::allow_empty`]).
+pub fn select<'r>(repo: &'r Repository, filter: &Filter) -> Result<Vec<git2::Submodule<'r>>> {
+    let mut selected = candidates(repo, filter)?;
+
+    if !filter.states.is_empty() {
+        let jobs = Config::load(repo)?.jobs;
+        let infos = gather(repo, &selected, jobs, filter.detail())?;
+        let mut keep = infos
+            .iter()
+            .map(|info| filter.states.iter().all(|s| s.holds(info)));
+        selected.retain(|_| keep.next().unwrap_or(false));
+    }
 
-        // Check if the submodule ref has uncommitted changes in the parent
-        info.parent_changed = check_parent_changed(repo, &info.path);
+    ensure_selected(filter, &selected)?;
+    Ok(selected)
+}
 
-        results.push(info);
+/// The one submodule `selector` picks out, for commands that work on a single
+/// submodule.
+pub fn select_one<'r>(repo: &'r Repository, selector: &str) -> Result<git2::Submodule<'r>> {
+    let filter = Filter::new(vec![selector.to_string()], Vec::new(), Vec::new());
+    let mut selected = select(repo, &filter)?;
+    if selected.len() > 1 {
+        bail!(
+            "'{}' matches {} submodules; name just one",
+            selector,
+            selected.len()
+        );
     }
+    Ok(selected.remove(0))
+}
 
+/// Discover the submodules selected by `filter` and gather their status on up
+/// to `jobs` threads, keeping .gitmodules order. Only the costlier fields in
+/// `detail` (plus any the filter's states need) are computed.
+pub fn discover(
+    repo: &Repository,
+    filter: &Filter,
+    jobs: usize,
+    detail: Detail,
+) -> Result<Vec<SubmoduleInfo>> {
+    let submodules = candidates(repo, filter)?;
+    let mut results = gather(repo, &submodules, jobs, detail.union(filter.detail()))?;
+    results.retain(|info| filter.states.iter().all(|s| s.holds(info)));
+
+    ensure_selected(filter, &results)?;
     Ok(results)
 }
 
-/// Check if the submodule has pending (staged or unstaged) changes in the parent repo's index.
-fn check_parent_changed(repo: &Repository, sm_path: &Path) -> bool {
-    let sm_path_str = match sm_path.to_str() {
-        Some(s) => s,
-        None => return false,
+/// Gather info for each submodule in parallel. A `Repository` can't be shared
+/// between threads, so every worker opens the parent itself.
+fn gather(
+    repo: &Repository,
+    submodules: &[git2::Submodule],
+    jobs: usize,
+    detail: Detail,
+) -> Result<Vec<SubmoduleInfo>> {
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+    let names: Vec<String> = submodules
+        .iter()
+        .map(|sm| sm.name().unwrap_or("").to_string())
+        .collect();
+
+    // Nothing worth caching when only the cheap fields are wanted
+    let cache = if detail.any() {
+        Cache::load(repo)
+    } else {
+        None
+    };
+
+    let results = parallel::map_init(
+        &names,
+        jobs,
+        || Repository::open(workdir),
+        |parent, name| {
+            let parent = parent
+                .as_ref()
+                .map_err(|e| anyhow!("Failed to open {}: {}", workdir.display(), e))?;
+            let sm = parent
+                .find_submodule(name)
+                .with_context(|| format!("Failed to read submodule '{}'", name))?;
+            info(parent, workdir, &sm, detail, cache.as_ref())
+        },
+    )
+    .into_iter()
+    .collect();
+
+    if let Some(cache) = cache {
+        // A cache that can't be written only costs speed next time
+        let _ = cache.save();
+    }
+
+    results
+}
+
+fn info(
+    repo: &Repository,
+    workdir: &Path,
+    sm: &git2::Submodule,
+    detail: Detail,
+    cache: Option<&Cache>,
+) -> Result<SubmoduleInfo> {
+    let name = sm.name().unwrap_or("").to_string();
+    let path = PathBuf::from(sm.path());
+    let url = sm.url().unwrap_or("").to_string();
+
+    // An uninitialised submodule is just an empty directory (or nothing), and
+    // one whose git directory was deleted has a `.git` file that won't open
+    let abs_path = workdir.join(&path);
+    let sub_repo = if abs_path.join(".git").exists() {
+        Some(Repository::open(&abs_path))
+    } else {
+        None
     };
 
-    // Check for staged changes (index vs HEAD)
-    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let mut info = match sub_repo {
+        Some(Ok(ref sub_repo)) => gather_info(&name, &path, &url, sub_repo, detail, cache)?,
+        _ => SubmoduleInfo::new(name, path, url),
+    };
 
-    let mut diff_opts = git2::DiffOptions::new();
-    diff_opts.pathspec(sm_path_str);
+    info.recorded_commit = recorded_commit(repo, &info.path);
+    info.staged_commit = staged_commit(repo, &info.path);
 
-    // Index vs HEAD (staged)
-    let staged = repo
-        .diff_tree_to_index(head_tree.as_ref(), None, Some(&mut diff_opts))
-        .ok()
-        .is_some_and(|d| d.deltas().count() > 0);
+    info.state = if info.staged_commit.is_none() {
+        SubmoduleState::NotInIndex
+    } else {
+        match sub_repo {
+            None => SubmoduleState::Uninitialised,
+            Some(Err(_)) => SubmoduleState::MissingGitdir,
+            Some(Ok(ref sub_repo)) if url_mismatch(repo, sub_repo, &info.url) => {
+                SubmoduleState::UrlMismatch
+            }
+            Some(Ok(_)) => SubmoduleState::Initialised,
+        }
+    };
 
-    // Workdir vs index (unstaged submodule ref change)
-    let mut diff_opts2 = git2::DiffOptions::new();
-    diff_opts2.pathspec(sm_path_str);
-    let unstaged = repo
-        .diff_index_to_workdir(None, Some(&mut diff_opts2))
-        .ok()
-        .is_some_and(|d| d.deltas().count() > 0);
+    // Check if the submodule ref has uncommitted changes in the parent
+    info.parent_changed = check_parent_changed(repo, &info.name);
+    info.groups = groups(repo, &info.name);
+
+    Ok(info)
+}
 
-    staged || unstaged
+/// Whether the submodule's `origin` differs from the URL in .gitmodules.
+fn url_mismatch(repo: &Repository, sub_repo: &Repository, url: &str) -> bool {
+    // Read the raw config value; `Remote::url` applies insteadOf rewrites
+    let Ok(origin) = sub_repo
+        .config()
+        .and_then(|c| c.get_string("remote.origin.url"))
+    else {
+        return false;
+    };
+
+    let normalise = |u: &str| {
+        let u = u.trim_end_matches('/');
+        u.strip_suffix(".git").unwrap_or(u).to_string()
+    };
+    normalise(&origin) != normalise(&resolve_url(repo, url))
 }
 
-fn gather_info(name: &str, path: &Path, url: &str, abs_path: &Path) -> Result<SubmoduleInfo> {
-    let sub_repo = Repository::open(abs_path)
-        .with_context(|| format!("Failed to open submodule repo at {}", abs_path.display()))?;
+/// Check if the submodule's gitlink has pending (staged or unstaged) changes
+/// in the parent repo. Uncommitted work inside the submodule doesn't count.
+fn check_parent_changed(repo: &Repository, name: &str) -> bool {
+    let Ok(status) = repo.submodule_status(name, git2::SubmoduleIgnore::Dirty) else {
+        return false;
+    };
+
+    // Index vs HEAD (staged) and the checked-out commit vs the index (unstaged)
+    status.intersects(
+        git2::SubmoduleStatus::INDEX_ADDED
+            | git2::SubmoduleStatus::INDEX_DELETED
+            | git2::SubmoduleStatus::INDEX_MODIFIED
+            | git2::SubmoduleStatus::WD_MODIFIED,
+    )
+}
 
+fn gather_info(
+    name: &str,
+    path: &Path,
+    url: &str,
+    sub_repo: &Repository,
+    detail: Detail,
+    cache: Option<&Cache>,
+) -> Result<SubmoduleInfo> {
+    let detached = sub_repo.head_detached().unwrap_or(false);
     let branch = sub_repo.head().ok().and_then(|h| {
         if h.is_branch() {
             h.shorthand().map(|s| s.to_string())
         } else {
             // Detached HEAD — show short commit hash
-            h.target().map(|oid| format!("{:.7}", oid))
+            h.target().map(|oid| abbrev(sub_repo, oid))
         }
     });
 
-    let (head_commit, head_message) = sub_repo
-        .head()
-        .ok()
-        .and_then(|h| h.peel_to_commit().ok())
-        .map(|c| {
-            let id = format!("{:.7}", c.id());
-            let msg = c.summary().unwrap_or("").to_string();
-            (Some(id), Some(msg))
-        })
-        .unwrap_or((None, None));
-
-    // Count staged, modified, untracked
-    let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
-    let statuses = sub_repo.statuses(Some(&mut opts)).unwrap_or_else(|_| {
-        // Return empty statuses on error
-        sub_repo.statuses(None).unwrap()
+    let head = sub_repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let head_commit = head.as_ref().map(|c| c.id());
+    let head_short = head_commit.map(|oid| abbrev(sub_repo, oid));
+    let head_message = head.as_ref().map(|c| c.summary().unwrap_or("").to_string());
+    let head_author = head.as_ref().map(|c| {
+        let author = c.author();
+        format!(
+            "{} <{}>",
+            author.name().unwrap_or(""),
+            author.email().unwrap_or("")
+        )
     });
+    let head_time = head.as_ref().map(|c| c.time());
+
+    let scan = match cache {
+        Some(cache) if detail.any() => cache.scan(name, sub_repo, detail),
+        _ => scan(sub_repo, detail),
+    };
+
+    Ok(SubmoduleInfo {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        url: url.to_string(),
+        groups: Vec::new(),                 // filled in by info()
+        state: SubmoduleState::Initialised, // refined by info()
+        branch,
+        detached,
+        head_commit,
+        head_short,
+        head_message,
+        head_author,
+        head_time,
+        recorded_commit: None, // filled in by info()
+        staged_commit: None,   // filled in by info()
+        is_dirty: scan.is_dirty,
+        ahead: scan.ahead,
+        behind: scan.behind,
+        staged: scan.staged,
+        modified: scan.modified,
+        untracked: scan.untracked,
+        parent_changed: false, // filled in by info()
+    })
+}
 
+/// The costlier parts of [`SubmoduleInfo`], computed as `detail` asks.
+pub(crate) fn scan(repo: &Repository, detail: Detail) -> Scan {
     let mut staged = 0;
     let mut modified = 0;
     let mut untracked = 0;
 
-    for entry in statuses.iter() {
-        let s = entry.status();
-        if s.intersects(
-            git2::Status::INDEX_NEW
-                | git2::Status::INDEX_MODIFIED
-                | git2::Status::INDEX_DELETED
-                | git2::Status::INDEX_RENAMED
-                | git2::Status::INDEX_TYPECHANGE,
-        ) {
+    let is_dirty = if (detail.counts || detail.untracked)
+        && let Some(counts) = git_status_counts(repo, detail.untracked)
+    {
+        // Let git use the submodule's fsmonitor or untracked cache
+        (staged, modified, untracked) = counts;
+        staged > 0 || modified > 0
+    } else if detail.counts || detail.untracked {
+        // Count staged, modified, and untracked only when asked
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(detail.untracked);
+        let statuses = repo.statuses(Some(&mut opts)).unwrap_or_else(|_| {
+            // Return empty statuses on error
+            repo.statuses(None).unwrap()
+        });
+
+        for entry in statuses.iter() {
+            let s = entry.status();
+            if s.intersects(
+                git2::Status::INDEX_NEW
+                    | git2::Status::INDEX_MODIFIED
+                    | git2::Status::INDEX_DELETED
+                    | git2::Status::INDEX_RENAMED
+                    | git2::Status::INDEX_TYPECHANGE,
+            ) {
+                staged += 1;
+            }
+            if s.intersects(
+                git2::Status::WT_MODIFIED | git2::Status::WT_DELETED | git2::Status::WT_RENAMED,
+            ) {
+                modified += 1;
+            }
+            if s.contains(git2::Status::WT_NEW) {
+                untracked += 1;
+            }
+        }
+
+        staged > 0 || modified > 0
+    } else {
+        detail.dirty && quick_dirty(repo)
+    };
+
+    // Ahead/behind upstream
+    let (ahead, behind) = if detail.upstream {
+        repo.head()
+            .ok()
+            .and_then(|head| {
+                let local_oid = head.target()?;
+                let branch_name = head.shorthand()?;
+                let upstream_name = format!("origin/{}", branch_name);
+                let upstream_ref = repo
+                    .find_reference(&format!("refs/remotes/{}", upstream_name))
+                    .ok()?;
+                let upstream_oid = upstream_ref.target()?;
+                repo.graph_ahead_behind(local_oid, upstream_oid).ok()
+            })
+            .unwrap_or((0, 0))
+    } else {
+        (0, 0)
+    };
+
+    Scan {
+        is_dirty,
+        staged,
+        modified,
+        untracked,
+        ahead,
+        behind,
+    }
+}
+
+/// Count changes with `git status` when the submodule has an fsmonitor or
+/// untracked cache configured, which libgit2 can't use. Returns `None` to fall
+/// back to libgit2.
+fn git_status_counts(repo: &Repository, untracked: bool) -> Option<(usize, usize, usize)> {
+    let config = repo.config().ok()?;
+    let fsmonitor = config
+        .get_string("core.fsmonitor")
+        .is_ok_and(|v| !v.is_empty() && v != "false");
+    let untracked_cache = config.get_bool("core.untrackedCache").unwrap_or(false);
+    if !fsmonitor && !untracked_cache {
+        return None;
+    }
+
+    let untracked_files = if untracked {
+        "--untracked-files=normal"
+    } else {
+        "--untracked-files=no"
+    };
+    let output = crate::git::output(
+        repo.workdir()?,
+        // Without renames, to count entries the way libgit2 does
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--no-renames",
+            untracked_files,
+        ],
+    )
+    .ok()?;
+
+    let (mut staged, mut modified, mut new) = (0, 0, 0);
+    for entry in output.split('\0') {
+        let mut codes = entry.chars();
+        let (Some(x), Some(y)) = (codes.next(), codes.next()) else {
+            continue;
+        };
+        if x == '?' {
+            new += 1;
+            continue;
+        }
+        if x != ' ' {
             staged += 1;
         }
-        if s.intersects(
-            git2::Status::WT_MODIFIED | git2::Status::WT_DELETED | git2::Status::WT_RENAMED,
-        ) {
+        if matches!(y, 'M' | 'D') {
             modified += 1;
         }
-        if s.contains(git2::Status::WT_NEW) {
-            untracked += 1;
+    }
+    Some((staged, modified, new))
+}
+
+/// Whether the index or tracked files differ from HEAD, stopping at the first
+/// change. Like git, files whose size and mtime still match the index are
+/// assumed unchanged without being read.
+fn quick_dirty(repo: &Repository) -> bool {
+    let Some(workdir) = repo.workdir() else {
+        return false;
+    };
+
+    // Index vs HEAD needs no filesystem access
+    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    if repo
+        .diff_tree_to_index(head_tree.as_ref(), None, None)
+        .is_ok_and(|d| d.deltas().len() > 0)
+    {
+        return true;
+    }
+
+    let Ok(index) = repo.index() else {
+        return false;
+    };
+    // Files written in the same second as the index can't be trusted by stat
+    // alone ("racily clean"), so those are always compared by content
+    let index_mtime = std::fs::metadata(repo.path().join("index"))
+        .and_then(|m| m.modified())
+        .ok();
+
+    for entry in index.iter() {
+        let skip = entry.mode == 0o160000
+            || entry.flags & git2::IndexEntryFlag::VALID.bits() != 0
+            || entry.flags_extended & git2::IndexEntryExtendedFlag::SKIP_WORKTREE.bits() != 0;
+        if skip {
+            continue;
+        }
+        let Ok(path) = std::str::from_utf8(&entry.path) else {
+            continue;
+        };
+
+        let unchanged = std::fs::symlink_metadata(workdir.join(path))
+            .is_ok_and(|meta| stat_matches(&entry, &meta, index_mtime));
+        if !unchanged && content_differs(repo, path) {
+            return true;
         }
     }
 
-    let is_dirty = staged > 0 || modified > 0;
+    false
+}
+
+fn stat_matches(
+    entry: &git2::IndexEntry,
+    meta: &std::fs::Metadata,
+    index_mtime: Option<std::time::SystemTime>,
+) -> bool {
+    let Ok(modified) = meta.modified() else {
+        return false;
+    };
+    let Ok(since_epoch) = modified.duration_since(std::time::UNIX_EPOCH) else {
+        return false;
+    };
 
-    // Ahead/behind upstream
-    let (ahead, behind) = sub_repo
-        .head()
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let executable = meta.permissions().mode() & 0o111 != 0;
+        if meta.is_file() && executable != (entry.mode == 0o100755) {
+            return false;
+        }
+    }
+
+    let racy = index_mtime.is_none_or(|index| modified >= index);
+    !racy
+        && entry.file_size == meta.len() as u32
+        && entry.mtime.seconds() as u64 == since_epoch.as_secs()
+        && entry.mtime.nanoseconds() == since_epoch.subsec_nanos()
+}
+
+/// Whether the tracked file at `path` differs from its index entry.
+fn content_differs(repo: &Repository, path: &str) -> bool {
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(path).disable_pathspec_match(true);
+    repo.diff_index_to_workdir(None, Some(&mut opts))
+        .map_or(true, |d| d.deltas().len() > 0)
+}
+
+/// The shortest abbreviation of `oid` that's unambiguous in `repo`, honouring
+/// `core.abbrev`. Falls back to seven characters for objects `repo` lacks.
+pub fn abbrev(repo: &Repository, oid: git2::Oid) -> String {
+    repo.find_object(oid, None)
         .ok()
-        .and_then(|head| {
-            let local_oid = head.target()?;
-            let branch_name = head.shorthand()?;
-            let upstream_name = format!("origin/{}", branch_name);
-            let upstream_ref = sub_repo
-                .find_reference(&format!("refs/remotes/{}", upstream_name))
-                .ok()?;
-            let upstream_oid = upstream_ref.target()?;
-            sub_repo.graph_ahead_behind(local_oid, upstream_oid).ok()
-        })
-        .unwrap_or((0, 0));
+        .and_then(|o| o.short_id().ok())
+        .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| format!("{:.7}", oid))
+}
 
-    Ok(SubmoduleInfo {
-        name: name.to_string(),
-        path: path.to_path_buf(),
-        url: url.to_string(),
-        branch,
-        head_commit,
-        head_message,
-        is_dirty,
-        ahead,
-        behind,
-        staged,
-        modified,
-        untracked,
-        parent_changed: false, // filled in by discover()
-    })
+/// Format a commit time in its own timezone, like git's `iso` date format.
+pub fn format_time(time: git2::Time) -> String {
+    let offset = chrono::FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+    match chrono::DateTime::from_timestamp(time.seconds(), 0) {
+        Some(utc) => utc
+            .with_timezone(&offset)
+            .format("%Y-%m-%d %H:%M:%S %z")
+            .to_string(),
+        None => time.seconds().to_string(),
+    }
+}
+
+/// Groups a submodule belongs to, from the multi-valued `submodule.<name>.group` key.
+pub fn groups(repo: &Repository, name: &str) -> Vec<String> {
+    let Some(workdir) = repo.workdir() else {
+        return Vec::new();
+    };
+    let Ok(config) = git2::Config::open(&workdir.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    let key = format!("submodule.{}.group", name);
+    let Ok(mut entries) = config.multivar(&key, None) else {
+        return Vec::new();
+    };
+
+    let mut groups = Vec::new();
+    while let Some(Ok(entry)) = entries.next() {
+        if let Some(value) = entry.value() {
+            groups.push(value.to_string());
+        }
+    }
+    groups
+}
+
+/// The commit recorded for the submodule at `sm_path` in the parent's HEAD tree.
+pub fn recorded_commit(repo: &Repository, sm_path: &Path) -> Option<git2::Oid> {
+    recorded_in(&repo.head().ok()?.peel_to_tree().ok()?, sm_path)
+}
+
+/// The commit recorded for the submodule at `sm_path` in a parent tree.
+pub fn recorded_in(tree: &git2::Tree, sm_path: &Path) -> Option<git2::Oid> {
+    let entry = tree.get_path(sm_path).ok()?;
+    (entry.kind() == Some(git2::ObjectType::Commit)).then(|| entry.id())
+}
+
+/// The commit staged for the submodule at `sm_path` in the parent's index.
+pub fn staged_commit(repo: &Repository, sm_path: &Path) -> Option<git2::Oid> {
+    let index = repo.index().ok()?;
+    let entry = index.get_path(sm_path, 0)?;
+    // Gitlinks are stored with the 0o160000 mode
+    (entry.mode == 0o160000).then_some(entry.id)
+}
+
+/// Resolve a relative .gitmodules URL against the parent's `origin`, as git does.
+pub fn resolve_url(repo: &Repository, url: &str) -> String {
+    if !url.starts_with("./") && !url.starts_with("../") {
+        return url.to_string();
+    }
+
+    let base = repo
+        .find_remote("origin")
+        .ok()
+        .and_then(|r| r.url().map(|u| u.to_string()))
+        .unwrap_or_else(|| {
+            repo.workdir()
+                .map(|w| w.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+
+    resolve_relative(&base, url)
+}
+
+/// Join a `./` or `../` URL onto `base`, popping one path component per `../`.
+fn resolve_relative(base: &str, url: &str) -> String {
+    let mut base = base.trim_end_matches('/').to_string();
+    let mut rest = url;
+    loop {
+        if let Some(r) = rest.strip_prefix("./") {
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("../") {
+            rest = r;
+            // Pop one path component, treating scp-style `host:path` like a slash
+            match base.rfind(['/', ':']) {
+                Some(i) => base.truncate(i + usize::from(base.as_bytes()[i] == b':')),
+                None => base.clear(),
+            }
+        } else {
+            break;
+        }
+    }
+
+    if base.ends_with(':') {
+        format!("{}{}", base, rest)
+    } else {
+        format!("{}/{}", base, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Detail, Filter, format_time, resolve_relative};
+    use std::path::Path;
+
+    fn selects(filter: &Filter, selector: &str, path: &str) -> bool {
+        filter
+            .compile(selector)
+            .unwrap()
+            .matches(path, Path::new(path))
+    }
+
+    #[test]
+    fn counts_cover_the_dirty_check() {
+        let counts = Detail {
+            counts: true,
+            ..Detail::default()
+        };
+        let dirty = Detail {
+            dirty: true,
+            ..Detail::default()
+        };
+        assert!(counts.covers(dirty));
+        assert!(!dirty.covers(counts));
+        assert!(!counts.covers(Detail::all()));
+        assert!(Detail::all().covers(counts.union(dirty)));
+    }
+
+    #[test]
+    fn formats_commit_times_in_their_own_timezone() {
+        assert_eq!(
+            format_time(git2::Time::new(1_700_000_000, 0)),
+            "2023-11-14 22:13:20 +0000"
+        );
+        assert_eq!(
+            format_time(git2::Time::new(1_700_000_000, -330)),
+            "2023-11-14 16:43:20 -0530"
+        );
+    }
+
+    #[test]
+    fn resolves_relative_urls_like_git() {
+        assert_eq!(
+            resolve_relative("https://github.com/org/parent.git", "../lib.git"),
+            "https://github.com/org/lib.git"
+        );
+        assert_eq!(
+            resolve_relative("https://github.com/org/parent", "./lib"),
+            "https://github.com/org/parent/lib"
+        );
+        assert_eq!(
+            resolve_relative("git@github.com:org/parent.git", "../../other/lib.git"),
+            "git@github.com:other/lib.git"
+        );
+    }
+
+    #[test]
+    fn globs_only_cross_separators_with_double_stars() {
+        let filter = Filter::new(Vec::new(), Vec::new(), Vec::new());
+        assert!(selects(&filter, "vendor/*", "vendor/foo"));
+        assert!(!selects(&filter, "vendor/*", "vendor/foo/bar"));
+        assert!(selects(&filter, "vendor/**", "vendor/foo/bar"));
+        assert!(selects(&filter, "**/bar", "vendor/foo/bar"));
+    }
+
+    #[test]
+    fn bare_paths_select_by_prefix_unless_exact() {
+        let filter = Filter::new(Vec::new(), Vec::new(), Vec::new());
+        assert!(selects(&filter, "vendor", "vendor/foo"));
+        assert!(!selects(&filter.clone().exact(), "vendor", "vendor/foo"));
+        assert!(selects(&filter.clone().exact(), "vendor", "vendor"));
+        assert!(selects(&filter.clone().exact(), "vendor/", "vendor/foo"));
+    }
+
+    #[test]
+    fn prefixes_stop_at_path_components() {
+        let filter = Filter::new(Vec::new(), Vec::new(), Vec::new());
+        assert!(!selects(&filter, "vendor", "vendorx"));
+        assert!(!selects(&filter, "vendor/", "vendorx"));
+        assert!(!selects(&filter.clone().exact(), "vendor", "vendorx"));
+        assert!(!selects(&filter, "vendor", "vendorx/foo"));
+    }
+
+    #[test]
+    fn names_are_never_patterns() {
+        let filter = Filter::names(Vec::new());
+        assert!(selects(&filter, "lib[1]", "lib[1]"));
+        assert!(!selects(&filter, "lib[1]", "lib1"));
+        assert!(!selects(&filter, "lib*", "libfoo"));
+        assert!(!selects(&filter, "lib", "lib/foo"));
+    }
 }
@@ -0,0 +1,330 @@
+use anyhow::{Result, bail};
+use std::cmp::Ordering;
+
+use crate::submodule::{self, Detail, SubmoduleInfo};
+
+/// Every field a template, sort key or column can refer to.
+pub const FIELDS: &[&str] = &[
+    "name",
+    "path",
+    "url",
+    "groups",
+    "state",
+    "branch",
+    "detached",
+    "head-commit",
+    "head-short",
+    "head-message",
+    "head-author",
+    "head-time",
+    "recorded-commit",
+    "staged-commit",
+    "is-dirty",
+    "ahead",
+    "behind",
+    "staged",
+    "modified",
+    "untracked",
+    "parent-changed",
+];
+
+/// The value of one field, keeping enough type to sort sensibly.
+pub enum Value {
+    Text(String),
+    Number(usize),
+    Flag(bool),
+    Time(Option<git2::Time>),
+}
+
+impl Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.cmp(b),
+            (Value::Flag(a), Value::Flag(b)) => a.cmp(b),
+            (Value::Time(a), Value::Time(b)) => a.map(|t| t.seconds()).cmp(&b.map(|t| t.seconds())),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+
+    /// Numbers right-align in columns; everything else reads left to right.
+    fn is_numeric(&self) -> bool {
+        matches!(self, Value::Number(_))
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Text(s) => f.write_str(s),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Flag(b) => write!(f, "{}", b),
+            Value::Time(Some(t)) => f.write_str(&submodule::format_time(*t)),
+            Value::Time(None) => Ok(()),
+        }
+    }
+}
+
+/// Look up `field` on `sm`. The field must be one of [`FIELDS`].
+fn value(sm: &SubmoduleInfo, field: &str) -> Value {
+    let text = |s: Option<&str>| Value::Text(s.unwrap_or("").to_string());
+    let oid = |o: Option<git2::Oid>| Value::Text(o.map(|o| o.to_string()).unwrap_or_default());
+
+    match field {
+        "name" => Value::Text(sm.name.clone()),
+        "path" => Value::Text(sm.path.display().to_string()),
+        "url" => Value::Text(sm.url.clone()),
+        "groups" => Value::Text(sm.groups.join(",")),
+        "state" => Value::Text(sm.state.label().to_string()),
+        "branch" => text(sm.branch.as_deref()),
+        "detached" => Value::Flag(sm.detached),
+        "head-commit" => oid(sm.head_commit),
+        "head-short" => text(sm.head_short.as_deref()),
+        "head-message" => text(sm.head_message.as_deref()),
+        "head-author" => text(sm.head_author.as_deref()),
+        "head-time" => Value::Time(sm.head_time),
+        "recorded-commit" => oid(sm.recorded_commit),
+        "staged-commit" => oid(sm.staged_commit),
+        "is-dirty" => Value::Flag(sm.is_dirty),
+        "ahead" => Value::Number(sm.ahead),
+        "behind" => Value::Number(sm.behind),
+        "staged" => Value::Number(sm.staged),
+        "modified" => Value::Number(sm.modified),
+        "untracked" => Value::Number(sm.untracked),
+        "parent-changed" => Value::Flag(sm.parent_changed),
+        _ => unreachable!("unknown field '{}'", field),
+    }
+}
+
+/// What has to be gathered to show `fields`. Sort keys may keep their `-`.
+pub fn detail<'a>(fields: impl IntoIterator<Item = &'a str>) -> Detail {
+    let mut detail = Detail::default();
+    for field in fields {
+        match field.trim_start_matches('-') {
+            "is-dirty" => detail.dirty = true,
+            "staged" | "modified" => detail.counts = true,
+            "untracked" => detail.untracked = true,
+            "ahead" | "behind" => detail.upstream = true,
+            _ => {}
+        }
+    }
+    detail
+}
+
+fn check_field(field: &str) -> Result<()> {
+    check_field_in(field, FIELDS)
+}
+
+/// Fail unless `key` is a valid [`sort`] key.
+pub fn check_sort_key(key: &str) -> Result<()> {
+    check_field(key.strip_prefix('-').unwrap_or(key))
+}
+
+/// Fail unless every one of `fields` can be shown by [`columns`].
+pub fn check_columns(fields: &[String]) -> Result<()> {
+    fields.iter().try_for_each(|f| check_field(f))
+}
+
+fn check_field_in(field: &str, fields: &[&str]) -> Result<()> {
+    if !fields.contains(&field) {
+        bail!(
+            "Unknown field '{}' (expected one of: {})",
+            field,
+            fields.join(", ")
+        );
+    }
+    Ok(())
+}
+
+pub enum Part {
+    Literal(String),
+    Field(String),
+}
+
+/// A `--format` template: literal text with `%(field)` placeholders, as in
+/// `git for-each-ref --format`. `%%` is a literal `%`.
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        Template::parse_fields(template, FIELDS)
+    }
+
+    /// Parse a template whose placeholders name one of `fields` rather than
+    /// a submodule field.
+    pub fn parse_fields(template: &str, fields: &[&str]) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(i) = rest.find('%') {
+            literal.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+
+            if let Some(r) = rest.strip_prefix('%') {
+                literal.push('%');
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix('(') {
+                let Some(end) = r.find(')') else {
+                    bail!("Unterminated placeholder in format '{}'", template);
+                };
+                let field = &r[..end];
+                check_field_in(field, fields)?;
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Field(field.to_string()));
+                rest = &r[end + 1..];
+            } else {
+                // A lone `%` is kept as-is
+                literal.push('%');
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Literal text and placeholders in template order, adjacent text merged.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// The fields the template refers to.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(f) => Some(f.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    pub fn render(&self, sm: &SubmoduleInfo) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(s) => s.clone(),
+                Part::Field(f) => value(sm, f).to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Sort by `key`, a field name optionally prefixed with `-` for descending
+/// order. The sort is stable, so ties keep .gitmodules order.
+pub fn sort(submodules: &mut [SubmoduleInfo], key: &str) -> Result<()> {
+    check_sort_key(key)?;
+    let (field, descending) = match key.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (key, false),
+    };
+
+    submodules.sort_by(|a, b| {
+        let order = value(a, field).cmp(&value(b, field));
+        if descending { order.reverse() } else { order }
+    });
+    Ok(())
+}
+
+/// Render the chosen fields as an aligned table with a header row.
+pub fn columns(submodules: &[SubmoduleInfo], fields: &[String]) -> Result<Vec<String>> {
+    check_columns(fields)?;
+
+    let rows: Vec<Vec<Value>> = submodules
+        .iter()
+        .map(|sm| fields.iter().map(|f| value(sm, f)).collect())
+        .collect();
+
+    let widths: Vec<usize> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            rows.iter()
+                .map(|row| row[i].to_string().chars().count())
+                .chain([f.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |cells: Vec<(String, bool)>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|((cell, numeric), &width)| {
+                if *numeric {
+                    format!("{:>width$}", cell)
+                } else {
+                    format!("{:<width$}", cell)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![line(
+        fields.iter().map(|f| (f.to_uppercase(), false)).collect(),
+    )];
+    for row in &rows {
+        lines.push(line(
+            row.iter()
+                .map(|v| (v.to_string(), v.is_numeric()))
+                .collect(),
+        ));
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn info(name: &str, ahead: usize) -> SubmoduleInfo {
+        let mut sm = SubmoduleInfo::new(
+            name.to_string(),
+            PathBuf::from(format!("libs/{}", name)),
+            format!("https://example.com/{}.git", name),
+        );
+        sm.ahead = ahead;
+        sm
+    }
+
+    #[test]
+    fn renders_placeholders_and_escapes() {
+        let template = Template::parse("%(name) %(path) 100%% %(ahead)").unwrap();
+        assert_eq!(template.render(&info("foo", 3)), "foo libs/foo 100% 3");
+    }
+
+    #[test]
+    fn rejects_unknown_and_unterminated_placeholders() {
+        assert!(Template::parse("%(nope)").is_err());
+        assert!(Template::parse("%(name").is_err());
+    }
+
+    #[test]
+    fn gathers_only_what_the_fields_need() {
+        let detail = detail(["name", "-behind"]);
+        assert!(detail.upstream && !detail.dirty && !detail.counts && !detail.untracked);
+    }
+
+    #[test]
+    fn sorts_numbers_numerically_and_descending() {
+        let mut sms = vec![info("a", 2), info("b", 10), info("c", 1)];
+        sort(&mut sms, "-ahead").unwrap();
+        let names: Vec<_> = sms.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn aligns_columns_by_characters() {
+        let sms = vec![info("café", 1), info("ab", 22)];
+        let lines = columns(&sms, &["name".to_string(), "ahead".to_string()]).unwrap();
+        assert_eq!(lines, ["NAME  AHEAD", "café      1", "ab       22"]);
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::cache;
+
+/// Quiet period after the last change before redrawing, so a checkout or a
+/// build touching thousands of files costs a single refresh.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Draw with `render`, then redraw whenever the parent's or a submodule's
+/// worktree or git directory changes, until interrupted.
+pub fn run(repo: &Repository, mut render: impl FnMut() -> Result<()>) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?;
+    let gitdir = repo.path();
+
+    // Redraws follow worktree edits, which the cache can't see
+    cache::disable();
+
+    // Reads show up as access events, so without dropping them every redraw
+    // would trigger the next one
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event
+            && !matches!(event.kind, EventKind::Access(_))
+        {
+            let _ = tx.send(event);
+        }
+    })?;
+    // The git directory holds every submodule's `.git/modules` entry too.
+    // One inside the worktree is picked up with the other `.git` directories.
+    if !gitdir.starts_with(workdir) {
+        watcher
+            .watch(gitdir, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", gitdir.display()))?;
+    }
+    let mut watched = BTreeMap::new();
+    watch_worktree(&mut watcher, &mut watched, repo, workdir)?;
+
+    // Note where every index stands so the first rewrite isn't mistaken for
+    // staging
+    let mut indexes = HashMap::new();
+    let subs = repo.submodules().unwrap_or_default();
+    for gitdir in [repo.path().to_path_buf()].into_iter().chain(
+        subs.iter()
+            .filter_map(|sm| Some(sm.open().ok()?.path().to_path_buf())),
+    ) {
+        index_unchanged(&mut indexes, &gitdir.join("index"));
+    }
+    let mut relevant = |event: &Event| {
+        event
+            .paths
+            .iter()
+            .any(|p| !is_noise(gitdir, p) && !index_unchanged(&mut indexes, p))
+    };
+
+    draw(&mut render)?;
+    while let Ok(event) = rx.recv() {
+        let mut changed = relevant(&event);
+        // Wait for a quiet spell before redrawing
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            changed |= relevant(&event);
+        }
+        if changed {
+            // Pick up directories created since the last look
+            watch_worktree(&mut watcher, &mut watched, repo, workdir)?;
+            draw(&mut render)?;
+        }
+    }
+    Ok(())
+}
+
+/// Bring the watches on the worktree in line with its current directories.
+fn watch_worktree(
+    watcher: &mut impl Watcher,
+    watched: &mut BTreeMap<PathBuf, RecursiveMode>,
+    repo: &Repository,
+    workdir: &Path,
+) -> Result<()> {
+    let mut dirs = BTreeMap::new();
+    walk(repo, workdir, &mut dirs);
+
+    for dir in watched.keys().filter(|d| !dirs.contains_key(*d)) {
+        // Watches on deleted directories are already gone
+        let _ = watcher.unwatch(dir);
+    }
+    for (dir, mode) in &dirs {
+        if !watched.contains_key(dir) {
+            watcher
+                .watch(dir, *mode)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+        }
+    }
+    *watched = dirs;
+    Ok(())
+}
+
+/// Collect `dir` and the directories beneath it that `repo` doesn't ignore,
+/// each to be watched on its own so ignored build trees (`target/`,
+/// `node_modules/`) cost no watches. Nested repositories are walked with
+/// their own ignore rules, and `.git` directories are watched whole.
+fn walk(repo: &Repository, dir: &Path, dirs: &mut BTreeMap<PathBuf, RecursiveMode>) {
+    dirs.insert(dir.to_path_buf(), RecursiveMode::NonRecursive);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let Some(workdir) = repo.workdir() else {
+        return;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            dirs.insert(path, RecursiveMode::Recursive);
+        } else if path.join(".git").exists()
+            && let Ok(nested) = Repository::open(&path)
+        {
+            walk(&nested, &path, dirs);
+        } else if !path
+            .strip_prefix(workdir)
+            .is_ok_and(|rel| repo.is_path_ignored(rel).unwrap_or(false))
+        {
+            walk(repo, &path, dirs);
+        }
+    }
+}
+
+/// Changes that can't affect the output: the status cache being written
+/// and git's short-lived lock files.
+fn is_noise(gitdir: &Path, path: &Path) -> bool {
+    path.starts_with(gitdir.join("dom")) || path.extension().is_some_and(|ext| ext == "lock")
+}
+
+/// Whether `path` is an index whose entries are the same as last time.
+/// `git status` rewrites the index to store stat data, the untracked cache
+/// or an fsmonitor token without anything being staged, and the redraw
+/// itself can run it.
+fn index_unchanged(seen: &mut HashMap<PathBuf, u64>, path: &Path) -> bool {
+    if path.file_name().is_none_or(|name| name != "index") {
+        return false;
+    }
+    let Ok(index) = git2::Index::open(path) else {
+        return false;
+    };
+    let mut hasher = DefaultHasher::new();
+    for entry in index.iter() {
+        (entry.path, entry.id, entry.mode, entry.flags).hash(&mut hasher);
+    }
+    let stamp = hasher.finish();
+    seen.insert(path.to_path_buf(), stamp) == Some(stamp)
+}
+
+fn draw(render: &mut impl FnMut() -> Result<()>) -> Result<()> {
+    // Clear the screen and home the cursor, as watch(1) does
+    print!("\x1b[2J\x1b[H");
+    println!(
+        "{}\n",
+        format!(
+            "Watching for changes, press Ctrl-C to stop. Last updated {}.",
+            chrono::Local::now().format("%H:%M:%S")
+        )
+        .dimmed()
+    );
+    // A failed refresh is shown in place; the next change may fix it
+    if let Err(e) = render() {
+        println!("{} {:#}", "Error:".red(), e);
+    }
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_noise;
+    use std::path::Path;
+
+    #[test]
+    fn ignores_the_status_cache_and_lock_files() {
+        let gitdir = Path::new("/repo/.git");
+        assert!(is_noise(
+            gitdir,
+            Path::new("/repo/.git/dom/status-cache.json")
+        ));
+        assert!(is_noise(
+            gitdir,
+            Path::new("/repo/.git/modules/a/index.lock")
+        ));
+        assert!(!is_noise(gitdir, Path::new("/repo/.git/modules/a/index")));
+        assert!(!is_noise(gitdir, Path::new("/repo/libs/a/src/main.rs")));
+    }
+}